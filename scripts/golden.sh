@@ -0,0 +1,35 @@
+#!/bin/sh
+# golden.sh - Compara el render sin ventana de cámaras fijas contra sus imágenes de referencia
+#
+# Uso: scripts/golden.sh            comprueba todas las cámaras (termina con código 1 si alguna difiere)
+#      scripts/golden.sh --update   vuelve a generar las referencias en golden/
+#
+# Las cámaras están en golden/cameras.txt; la referencia de cada una es golden/<nombre>.png
+# (`cargo test` hace la misma comparación, ver headless.rs)
+
+set -e
+cd "$(dirname "$0")/.."
+
+cargo build --release --quiet
+BIN=target/release/MazeRunner
+OUT=target/golden # los renders de la última comprobación, para mirar los que difieren
+mkdir -p "$OUT" golden
+
+failed=0
+grep -v '^#' golden/cameras.txt | while read -r name level x y angle options; do
+    [ -z "$name" ] && continue
+    golden="golden/$name.png"
+    if [ "$1" = "--update" ]; then
+        "$BIN" --headless "$level" "$x" "$y" "$angle" "$golden" $options
+        echo "Actualizada: $golden"
+    elif [ ! -f "$golden" ]; then
+        echo "Falta la referencia $golden (generarla con --update)"
+        exit 1
+    elif "$BIN" --headless "$level" "$x" "$y" "$angle" "$OUT/$name.png" $options --golden "$golden"; then
+        echo "OK: $name"
+    else
+        echo "DIFIERE: $name (render en $OUT/$name.png)"
+        exit 1
+    fi
+done || failed=1
+exit $failed
@@ -0,0 +1,144 @@
+// capture.rs - Capturas de pantalla y secuencias de frames
+//
+// Las capturas son el frame final (con el HUD) y se guardan como PNG en `directory`:
+//   captura_<fecha>.png                  una captura suelta
+//   secuencia_<fecha>/frame_00000.png    uno de cada `every_nth` frames mientras se graba
+
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::framebuffer::Framebuffer;
+
+pub struct Capture {
+    directory: PathBuf,
+    every_nth: u32,
+    recording: Option<Recording>,
+}
+
+// Secuencia en curso
+struct Recording {
+    directory: PathBuf,
+    frames_seen: u32,
+    frames_saved: u32,
+}
+
+impl Capture {
+    pub fn new(directory: &str, every_nth: u32) -> Self {
+        Capture {
+            directory: PathBuf::from(directory),
+            every_nth: every_nth.max(1),
+            recording: None,
+        }
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    // Guardar el frame actual; devuelve la ruta del archivo
+    pub fn screenshot(&self, framebuffer: &Framebuffer) -> io::Result<PathBuf> {
+        fs::create_dir_all(&self.directory)?;
+        let path = self.directory.join(format!("captura_{}.png", timestamp()));
+        framebuffer.render_to_file(&path.to_string_lossy());
+        Ok(path)
+    }
+
+    // Empezar una secuencia nueva o terminar la actual; devuelve su carpeta al empezar
+    pub fn toggle_recording(&mut self) -> io::Result<Option<PathBuf>> {
+        if self.recording.take().is_some() {
+            return Ok(None);
+        }
+        let directory = self.directory.join(format!("secuencia_{}", timestamp()));
+        fs::create_dir_all(&directory)?;
+        self.recording = Some(Recording {
+            directory: directory.clone(),
+            frames_seen: 0,
+            frames_saved: 0,
+        });
+        Ok(Some(directory))
+    }
+
+    // Llamar una vez por frame, con el frame ya compuesto: guarda uno de cada `every_nth`
+    pub fn record_frame(&mut self, framebuffer: &Framebuffer) {
+        let Some(recording) = self.recording.as_mut() else {
+            return;
+        };
+        if recording.frames_seen % self.every_nth == 0 {
+            let path = recording.directory.join(format!("frame_{:05}.png", recording.frames_saved));
+            framebuffer.render_to_file(&path.to_string_lossy());
+            recording.frames_saved += 1;
+        }
+        recording.frames_seen += 1;
+    }
+}
+
+// Fecha y hora actual (UTC) para los nombres de archivo: 2024-05-31_18-04-09-250
+fn timestamp() -> String {
+    let elapsed = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    let seconds = elapsed.as_secs();
+    let (year, month, day) = civil_from_days((seconds / 86_400) as i64);
+    let time_of_day = seconds % 86_400;
+    format!(
+        "{:04}-{:02}-{:02}_{:02}-{:02}-{:02}-{:03}",
+        year,
+        month,
+        day,
+        time_of_day / 3600,
+        time_of_day / 60 % 60,
+        time_of_day % 60,
+        elapsed.subsec_millis()
+    )
+}
+
+// Días desde 1970-01-01 a (año, mes, día) del calendario gregoriano (algoritmo de H. Hinnant)
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn civil_from_days_known_dates() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(-1), (1969, 12, 31));
+        assert_eq!(civil_from_days(59), (1970, 3, 1));
+        assert_eq!(civil_from_days(11_016), (2000, 2, 29));
+        assert_eq!(civil_from_days(19_723), (2024, 1, 1));
+    }
+
+    #[test]
+    fn civil_from_days_advances_one_day_at_a_time() {
+        let days_in_month = |year: i64, month: u32| match month {
+            2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+            2 => 28,
+            4 | 6 | 9 | 11 => 30,
+            _ => 31,
+        };
+        let mut previous = civil_from_days(-800);
+        for days in -799..60_000 {
+            let (year, month, day) = civil_from_days(days);
+            let expected = if previous.2 < days_in_month(previous.0, previous.1) {
+                (previous.0, previous.1, previous.2 + 1)
+            } else if previous.1 < 12 {
+                (previous.0, previous.1 + 1, 1)
+            } else {
+                (previous.0 + 1, 1, 1)
+            };
+            assert_eq!((year, month, day), expected, "día {}", days);
+            previous = (year, month, day);
+        }
+    }
+}
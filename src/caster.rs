@@ -2,10 +2,34 @@
 
 use raylib::color::Color;
 
+use crate::decals::{blend_decal, Decals, Face};
 use crate::framebuffer::Framebuffer;
-use crate::maze::load_maze;
+use crate::lights::{apply_light, LightMap};
+use crate::maze::{is_open_cell, is_portal_char, Door, Doors, Portal, Portals, DOOR_CHAR, DOOR_JAMB_CHAR, MIRROR_CHAR, OUTDOOR_CHAR};
 use crate::player::Player;
-use crate::textures::TextureManager;
+use crate::textures::{TextureManager, TextureSampling};
+use crate::worker_pool::WorkerPool;
+
+// Color de la niebla en zonas exteriores (el horizonte del cielo panorámico)
+const SKY_FOG_COLOR: Color = Color::new(46, 48, 74, 255);
+
+// Sombreado por lado: las caras este/oeste ('v') se ven más oscuras que las norte/sur ('h')
+const SIDE_SHADE_V: f32 = 0.7;
+const SIDE_SHADE_H: f32 = 1.0;
+
+// Oclusión ambiental en esquinas interiores y en el borde con el suelo
+const AO_RADIUS: f32 = 0.2; // fracción del bloque afectada
+const AO_STRENGTH: f32 = 0.5; // oscurecimiento máximo
+
+// Altura de las paredes en unidades del mundo (igual al tamaño de bloque)
+const WALL_HEIGHT: f32 = 100.0;
+
+// Espejos: rebotes máximos por rayo y tinte que aplica cada rebote (R, G, B)
+const MAX_MIRROR_BOUNCES: usize = 3;
+const MIRROR_TINT: [f32; 3] = [0.75, 0.85, 0.95];
+
+// Portales que puede atravesar un rayo antes de rendirse (dos portales enfrentados no terminan nunca)
+const MAX_PORTAL_HOPS: usize = 4;
 
 pub struct Intersect {
     pub distance: f32,
@@ -13,28 +37,99 @@ pub struct Intersect {
     pub hit_x: f32,
     pub hit_y: f32,
     pub wall_side: char, // 'h' for horizontal, 'v' for vertical
+    pub texture_offset: f32, // desplazamiento de la textura a lo largo de la pared (puertas deslizantes)
+}
+
+// Intersección del rayo con el plano central de una puerta
+// Retorna None si el rayo no cruza el plano dentro de la celda o si pasa por el hueco abierto
+fn intersect_door(door: &Door, origin_x: f32, origin_y: f32, cos_a: f32, sin_a: f32, block_size: usize) -> Option<Intersect> {
+    let block_size_f = block_size as f32;
+    let cell_x = door.col as f32 * block_size_f;
+    let cell_y = door.row as f32 * block_size_f;
+    let open_offset = door.open_amount * block_size_f;
+
+    if door.horizontal {
+        if sin_a.abs() < 1e-6 {
+            return None;
+        }
+        let plane_y = cell_y + block_size_f / 2.0;
+        let t = (plane_y - origin_y) / sin_a;
+        let hit_x = origin_x + t * cos_a;
+        // La hoja se desliza hacia la derecha (x crece), dejando libre [cell_x, cell_x + open_offset)
+        if t < 0.0 || hit_x < cell_x + open_offset || hit_x >= cell_x + block_size_f {
+            return None;
+        }
+        Some(Intersect {
+            distance: t,
+            impact: DOOR_CHAR,
+            hit_x,
+            hit_y: plane_y,
+            wall_side: 'h',
+            texture_offset: open_offset,
+        })
+    } else {
+        if cos_a.abs() < 1e-6 {
+            return None;
+        }
+        let plane_x = cell_x + block_size_f / 2.0;
+        let t = (plane_x - origin_x) / cos_a;
+        let hit_y = origin_y + t * sin_a;
+        // La hoja se desliza hacia abajo (y crece), dejando libre [cell_y, cell_y + open_offset)
+        if t < 0.0 || hit_y < cell_y + open_offset || hit_y >= cell_y + block_size_f {
+            return None;
+        }
+        Some(Intersect {
+            distance: t,
+            impact: DOOR_CHAR,
+            hit_x: plane_x,
+            hit_y,
+            wall_side: 'v',
+            texture_offset: open_offset,
+        })
+    }
 }
 
 pub fn cast_ray(
     framebuffer: &mut Framebuffer,
-    maze: &Vec<Vec<char>>,
+    maze: &[Vec<char>],
+    doors: &Doors,
     player: &Player,
     a: f32,
     block_size: usize,
     draw_line: bool,
+) -> Intersect {
+    framebuffer.set_current_color(Color::WHITESMOKE);
+
+    march_ray(maze, doors, player.pos.x, player.pos.y, a, block_size, |x, y| {
+        if draw_line {
+            framebuffer.set_pixel(x, y);
+        }
+    })
+}
+
+// Avanza el rayo desde (origin_x, origin_y) hasta chocar con una pared o puerta
+// No necesita el framebuffer, así que se puede llamar desde varios hilos a la vez;
+// `visit` recibe cada punto recorrido (para dibujar el rayo en la vista 2D)
+fn march_ray(
+    maze: &[Vec<char>],
+    doors: &Doors,
+    origin_x: f32,
+    origin_y: f32,
+    a: f32,
+    block_size: usize,
+    mut visit: impl FnMut(u32, u32),
 ) -> Intersect {
     let mut d = 0.0;
+    let mut previous_cell = ' ';
     let step_size = 3.0; // Balance entre precisión y rendimiento (optimizado)
 
-    framebuffer.set_current_color(Color::WHITESMOKE);
-    
     // Precalcular valores trigonométricos
     let cos_a = a.cos();
     let sin_a = a.sin();
 
     loop {
-        let x = player.pos.x + d * cos_a;
-        let y = player.pos.y + d * sin_a;
+        let x = origin_x + d * cos_a;
+        let y = origin_y + d * sin_a;
         
         let grid_x = x as usize;
         let grid_y = y as usize;
@@ -51,13 +146,22 @@ pub fn cast_ray(
                 hit_x: x,
                 hit_y: y,
                 wall_side: 'v',
+                texture_offset: 0.0,
             };
         }
 
         let cell = maze[j][i];
+
+        // Las puertas se dibujan en el centro de la celda; el rayo sigue de largo por el hueco abierto
+        if cell == DOOR_CHAR
+            && let Some(door) = doors.get(i, j)
+            && let Some(intersect) = intersect_door(door, origin_x, origin_y, cos_a, sin_a, block_size)
+        {
+            return intersect;
+        }
         
-        // Los triggers ('t', 's', 'c') son transparentes y atravesables, tratarlos como espacios vacíos
-        if cell != ' ' && cell != 't' && cell != 's' && cell != 'c' {
+        // Las celdas vacías (triggers y exteriores incluidos) se atraviesan; las puertas ya se trataron arriba
+        if !is_open_cell(cell) && cell != DOOR_CHAR {
             // Determinar qué lado de la pared fue golpeado de manera más precisa
             let cell_x = (i * block_size) as f32;
             let cell_y = (j * block_size) as f32;
@@ -77,130 +181,699 @@ pub fn cast_ray(
                 'h' // pared horizontal (arriba o abajo)
             };
 
+            // Las paredes vistas desde dentro de una celda de puerta son el marco de la puerta
+            let impact = if previous_cell == DOOR_CHAR { DOOR_JAMB_CHAR } else { cell };
+
             return Intersect {
                 distance: d,
-                impact: cell,
+                impact,
                 hit_x: x,
                 hit_y: y,
                 wall_side,
+                texture_offset: 0.0,
             };
         }
 
-        if draw_line {
-            framebuffer.set_pixel(grid_x as u32, grid_y as u32);
-        }
+        visit(grid_x as u32, grid_y as u32);
+
+        previous_cell = cell;
 
         d += step_size;
     }
 }
 
-pub fn render3d(framebuffer: &mut Framebuffer, player: &Player, texture_manager: &TextureManager, maze: &Vec<Vec<char>>) -> Vec<f32> {
+// Tramo recto de un rayo; cada rebote en un espejo empieza un tramo nuevo
+#[derive(Clone, Copy)]
+struct RaySegment {
+    origin_x: f32,
+    origin_y: f32,
+    angle: f32,
+    cos_a: f32,
+    sin_a: f32,
+    start_distance: f32, // distancia recorrida antes de este tramo
+    bounces: usize,
+}
+
+impl RaySegment {
+    fn new(origin_x: f32, origin_y: f32, angle: f32, start_distance: f32, bounces: usize) -> Self {
+        RaySegment {
+            origin_x,
+            origin_y,
+            angle,
+            cos_a: angle.cos(),
+            sin_a: angle.sin(),
+            start_distance,
+            bounces,
+        }
+    }
+}
+
+// Primer espejo que ve una columna de pantalla
+// Los sprites reflejados se dibujan en la posición simétrica respecto al plano del espejo
+#[derive(Clone, Copy, PartialEq)]
+pub struct Reflection {
+    pub wall_side: char,      // 'v': plano x = plane, 'h': plano y = plane
+    pub plane: f32,
+    pub mirror_distance: f32, // distancia del jugador al espejo
+    pub depth: f32,           // distancia total hasta lo que se ve en el reflejo
+}
+
+impl Reflection {
+    // Posición virtual de un punto visto en este espejo
+    pub fn mirror_point(&self, x: f32, y: f32) -> (f32, f32) {
+        if self.wall_side == 'v' {
+            (2.0 * self.plane - x, y)
+        } else {
+            (x, 2.0 * self.plane - y)
+        }
+    }
+
+    // Si el punto está del mismo lado del espejo que el observador (solo así puede reflejarse)
+    pub fn same_side(&self, x: f32, y: f32, viewer_x: f32, viewer_y: f32) -> bool {
+        if self.wall_side == 'v' {
+            (x - self.plane).signum() == (viewer_x - self.plane).signum()
+        } else {
+            (y - self.plane).signum() == (viewer_y - self.plane).signum()
+        }
+    }
+
+    // Si dos columnas ven el mismo espejo
+    pub fn same_mirror(&self, other: &Reflection) -> bool {
+        self.wall_side == other.wall_side && self.plane == other.plane
+    }
+}
+
+// Tiñe un color según la cantidad de rebotes en espejos
+pub fn tint_reflection(color: Color, bounces: usize) -> Color {
+    if bounces == 0 {
+        return color;
+    }
+    let exponent = bounces as i32;
+    Color::new(
+        (color.r as f32 * MIRROR_TINT[0].powi(exponent)) as u8,
+        (color.g as f32 * MIRROR_TINT[1].powi(exponent)) as u8,
+        (color.b as f32 * MIRROR_TINT[2].powi(exponent)) as u8,
+        color.a,
+    )
+}
+
+// Rayo completo: el impacto final y los tramos recorridos entre espejos
+struct TracedRay {
+    intersect: Intersect,
+    segments: Vec<RaySegment>,
+    reflection: Option<Reflection>,
+}
+
+impl TracedRay {
+    // Tramo del rayo que contiene el punto a `distance` del jugador
+    fn segment_at(&self, distance: f32) -> &RaySegment {
+        self.segments
+            .iter()
+            .rev()
+            .find(|segment| segment.start_distance <= distance)
+            .unwrap_or(&self.segments[0])
+    }
+
+    // Punto del mundo a `distance` del jugador siguiendo el rayo, y sus rebotes hasta ahí
+    fn point_at(&self, distance: f32) -> (f32, f32, &RaySegment) {
+        let segment = self.segment_at(distance);
+        let local = distance - segment.start_distance;
+        (segment.origin_x + local * segment.cos_a, segment.origin_y + local * segment.sin_a, segment)
+    }
+
+    fn last_segment(&self) -> &RaySegment {
+        &self.segments[self.segments.len() - 1]
+    }
+}
+
+// Rebote en la cara de un espejo: devuelve el plano del espejo, la distancia exacta a lo largo
+// del tramo (el rayo avanza a pasos y puede haber entrado en la celda) y el ángulo reflejado
+// Retorna None si el rayo es casi paralelo al espejo
+fn mirror_bounce(intersect: &Intersect, segment: &RaySegment, block_size: usize) -> Option<(f32, f32, f32)> {
+    let block_size_f = block_size as f32;
+    let cell_x = (intersect.hit_x / block_size_f).floor() * block_size_f;
+    let cell_y = (intersect.hit_y / block_size_f).floor() * block_size_f;
+
+    let (plane, t, reflected_angle) = if intersect.wall_side == 'v' {
+        let plane = if segment.cos_a > 0.0 { cell_x } else { cell_x + block_size_f };
+        (plane, (plane - segment.origin_x) / segment.cos_a, std::f32::consts::PI - segment.angle)
+    } else {
+        let plane = if segment.sin_a > 0.0 { cell_y } else { cell_y + block_size_f };
+        (plane, (plane - segment.origin_y) / segment.sin_a, -segment.angle)
+    };
+
+    if !t.is_finite() || t < 0.0 {
+        return None;
+    }
+    Some((plane, t, reflected_angle))
+}
+
+// Distancia a lo largo del tramo hasta el plano de entrada de un portal
+fn portal_crossing(portals: &Portals, portal: &Portal, segment: &RaySegment, block_size: usize) -> Option<f32> {
+    let (entry_x, entry_y) = portals.entrance(portal, block_size);
+    let t = if portal.facing.0 != 0 {
+        (entry_x - segment.origin_x) / segment.cos_a
+    } else {
+        (entry_y - segment.origin_y) / segment.sin_a
+    };
+
+    if !t.is_finite() || t < 0.0 {
+        return None;
+    }
+    Some(t)
+}
+
+// Lanza el rayo desde el jugador, lo hace rebotar en los espejos (hasta MAX_MIRROR_BOUNCES)
+// y lo continúa desde la pareja de cada portal que atraviesa (hasta MAX_PORTAL_HOPS)
+fn trace_ray(maze: &[Vec<char>], doors: &Doors, portals: &Portals, player: &Player, a: f32, block_size: usize) -> TracedRay {
+    let mut segments = vec![RaySegment::new(player.pos.x, player.pos.y, a, 0.0, 0)];
+    let mut reflection: Option<Reflection> = None;
+    let mut portal_hops = 0;
+
+    loop {
+        let segment = segments[segments.len() - 1];
+        let mut intersect = march_ray(maze, doors, segment.origin_x, segment.origin_y, segment.angle, block_size, |_, _| {});
+        intersect.distance += segment.start_distance;
+
+        if is_portal_char(intersect.impact) {
+            let col = (intersect.hit_x / block_size as f32) as usize;
+            let row = (intersect.hit_y / block_size as f32) as usize;
+
+            // Continuar el rayo desde la entrada de la pareja, con el ángulo girado
+            if portal_hops < MAX_PORTAL_HOPS
+                && let Some(portal) = portals.get(col, row)
+                && let Some(t) = portal_crossing(portals, portal, &segment, block_size)
+                && let Some((exit_x, exit_y, exit_angle)) = portals.transform(
+                    col,
+                    row,
+                    segment.origin_x + t * segment.cos_a,
+                    segment.origin_y + t * segment.sin_a,
+                    segment.angle,
+                    block_size,
+                )
+            {
+                portal_hops += 1;
+                let mut next = RaySegment::new(exit_x, exit_y, exit_angle, segment.start_distance + t, segment.bounces);
+                next.origin_x += next.cos_a * 0.01;
+                next.origin_y += next.sin_a * 0.01;
+                segments.push(next);
+                continue;
+            }
+
+            // Portal sin pareja o demasiados saltos: no se ve nada más allá
+            intersect.impact = ' ';
+        }
+
+        if intersect.impact == MIRROR_CHAR
+            && segment.bounces < MAX_MIRROR_BOUNCES
+            && let Some((plane, t, reflected_angle)) = mirror_bounce(&intersect, &segment, block_size)
+        {
+            let hit_x = segment.origin_x + t * segment.cos_a;
+            let hit_y = segment.origin_y + t * segment.sin_a;
+            let distance = segment.start_distance + t;
+
+            // Tras cruzar un portal el espejo está en otro sistema de coordenadas y los sprites
+            // reflejados no coincidirían: solo se registran los espejos vistos directamente
+            if portal_hops == 0 && segment.bounces == 0 {
+                reflection = Some(Reflection {
+                    wall_side: intersect.wall_side,
+                    plane,
+                    mirror_distance: distance,
+                    depth: f32::INFINITY,
+                });
+            }
+
+            // Separar el nuevo origen un poco del espejo para no volver a chocar con él
+            let mut next = RaySegment::new(hit_x, hit_y, reflected_angle, distance, segment.bounces + 1);
+            next.origin_x += next.cos_a * 0.01;
+            next.origin_y += next.sin_a * 0.01;
+            segments.push(next);
+            continue;
+        }
+
+        if let Some(reflection) = reflection.as_mut() {
+            reflection.depth = if intersect.impact == ' ' { f32::INFINITY } else { intersect.distance };
+        }
+        return TracedRay { intersect, segments, reflection };
+    }
+}
+
+// Columna de pared ya sombreada, lista para copiarse al framebuffer
+struct WallColumn {
+    top: usize,
+    colors: Vec<Color>,
+    distance: f32,
+}
+
+// Resultado de un rayo: el cielo visible sobre la pared (filas desde 0, None = techo), la pared
+// y el piso iluminado bajo ella (filas desde `floor_top`, None = piso sin luz extra)
+struct RenderedColumn {
+    sky: Vec<Option<Color>>,
+    wall: Option<WallColumn>,
+    floor_top: usize,
+    floor: Vec<Option<Color>>,
+    reflection: Option<Reflection>,
+}
+
+// Verificar si un punto del mundo cae en una celda exterior (sin techo)
+fn is_outdoor_at(maze: &[Vec<char>], x: f32, y: f32, block_size: usize) -> bool {
+    if x < 0.0 || y < 0.0 {
+        return false;
+    }
+    let i = (x / block_size as f32) as usize;
+    let j = (y / block_size as f32) as usize;
+    maze.get(j).and_then(|row| row.get(i)) == Some(&OUTDOOR_CHAR)
+}
+
+// Celdas sólidas para la oclusión ambiental (fuera del laberinto cuenta como pared)
+fn is_solid_cell(maze: &[Vec<char>], col: isize, row: isize) -> bool {
+    if col < 0 || row < 0 {
+        return true;
+    }
+    match maze.get(row as usize).and_then(|r| r.get(col as usize)) {
+        Some(&cell) => !is_open_cell(cell) && cell != DOOR_CHAR && !is_portal_char(cell),
+        None => true,
+    }
+}
+
+// Esquinas interiores a lo largo de la pared
+// Devuelve si hay una pared perpendicular en cada extremo: (u = 0, u = 1)
+fn corner_occlusion(maze: &[Vec<char>], intersect: &Intersect, front_x: f32, front_y: f32, block_size: usize) -> (bool, bool) {
+    let col = (front_x / block_size as f32).floor() as isize;
+    let row = (front_y / block_size as f32).floor() as isize;
+
+    // Vecinos de la celda frente a la pared, en la dirección en la que corre la pared
+    if intersect.wall_side == 'v' {
+        (is_solid_cell(maze, col, row - 1), is_solid_cell(maze, col, row + 1))
+    } else {
+        (is_solid_cell(maze, col - 1, row), is_solid_cell(maze, col + 1, row))
+    }
+}
+
+// Factor de oclusión para un texel de pared en coordenadas (u, v) del bloque, ambas en [0, 1]
+// v = 0 arriba, v = 1 en el suelo
+fn ambient_occlusion(u: f32, v: f32, corners: (bool, bool)) -> f32 {
+    let edge = |distance: f32| {
+        let t = (1.0 - distance / AO_RADIUS).max(0.0);
+        t * t
+    };
+
+    let mut occlusion = edge(1.0 - v); // borde con el suelo
+    if corners.0 {
+        occlusion = occlusion.max(edge(u));
+    }
+    if corners.1 {
+        occlusion = occlusion.max(edge(1.0 - u));
+    }
+
+    1.0 - AO_STRENGTH * occlusion
+}
+
+// Datos de solo lectura que comparten todos los hilos de render3d (la vista más lo que se
+// calcula una vez por frame)
+struct RenderContext<'a> {
+    player: &'a Player,
+    texture_manager: &'a TextureManager,
+    maze: &'a [Vec<char>],
+    doors: &'a Doors,
+    portals: &'a Portals,
+    decals: &'a Decals,
+    light_map: &'a LightMap,
+    num_rays: usize,
+    screen_height: usize,
+    horizon: f32,    // fila del horizonte (se desplaza al mirar arriba/abajo)
+    eye_height: f32, // altura de los ojos del jugador (las paredes miden 100)
+    texture_sampling: TextureSampling,
+}
+
+// Lanza el rayo `i` y sombrea su columna (cielo, pared y piso)
+// Solo lee datos compartidos, por eso cada hilo puede procesar su propia franja de columnas
+fn render_column(i: usize, ctx: &RenderContext) -> RenderedColumn {
     let block_size = 100;
+    let player = ctx.player;
+    let screen_height = ctx.screen_height;
+
+    let current_ray = i as f32 / ctx.num_rays as f32;
+    let a = player.a - (player.fov / 2.0) + (player.fov * current_ray);
+    let ray = trace_ray(ctx.maze, ctx.doors, ctx.portals, player, a, block_size);
+
+    // La pared se sombrea según el último tramo del rayo y se tiñe por cada rebote
+    let last_segment = *ray.last_segment();
+    let mut wall = shade_wall_column(&ray.intersect, last_segment.angle, ctx);
+    if let Some(wall) = wall.as_mut() {
+        for color in wall.colors.iter_mut() {
+            *color = tint_reflection(*color, last_segment.bounces);
+        }
+    }
+
+    // El techo visible termina donde empieza la pared (o en el horizonte si no hay pared)
+    let horizon_row = (ctx.horizon.max(0.0) as usize).min(screen_height);
+    let ceiling_end = wall.as_ref().map_or(horizon_row, |wall| wall.top.min(screen_height));
+    let sky = render_sky_column(&ray, ceiling_end, ctx);
+
+    // El piso empieza donde termina la pared
+    let floor_top = wall.as_ref().map_or(horizon_row, |wall| (wall.top + wall.colors.len()).min(screen_height));
+    let floor = render_floor_column(&ray, floor_top, ctx);
+
+    RenderedColumn { sky, wall, floor_top, floor, reflection: ray.reflection }
+}
+
+// Distancia a la que la fila `y` de la pantalla toca el piso (bajo el horizonte) o el techo (sobre él)
+// Es la inversa de la altura del stake: una pared a esa distancia tiene su borde justo en esa fila
+pub fn row_distance(y: usize, screen_height: usize, horizon: f32, eye_height: f32) -> f32 {
+    let hh = screen_height as f32 / 2.0;
+    let distance_to_projection_plane = 100.0;
+    let from_horizon = y as f32 + 0.5 - horizon;
+    if from_horizon > 0.0 {
+        eye_height * hh * distance_to_projection_plane / (WALL_HEIGHT * from_horizon)
+    } else if from_horizon < 0.0 {
+        (WALL_HEIGHT - eye_height) * hh * distance_to_projection_plane / (WALL_HEIGHT * -from_horizon)
+    } else {
+        f32::INFINITY
+    }
+}
+
+// Proyecta cada fila del piso sobre el mundo y aplica el mapa de luz
+// Solo devuelve color donde las luces aportan algo; el resto del piso ya lo pintó render_world
+fn render_floor_column(ray: &TracedRay, floor_top: usize, ctx: &RenderContext) -> Vec<Option<Color>> {
+    let light_map = ctx.light_map;
+    if light_map.is_empty() {
+        return Vec::new();
+    }
+
+    let screen_height = ctx.screen_height;
+    let floor_color = Color::GRAY;
+
+    (floor_top..screen_height)
+        .map(|y| {
+            let below_horizon = y as f32 + 0.5 - ctx.horizon;
+            if below_horizon <= 0.0 {
+                return None;
+            }
+
+            let (world_x, world_y, segment) = ray.point_at(row_distance(y, screen_height, ctx.horizon, ctx.eye_height));
+            let light = light_map.sample(world_x, world_y);
+
+            if light[0] + light[1] + light[2] < 0.01 {
+                return None;
+            }
+            Some(tint_reflection(apply_light(floor_color, light), segment.bounces))
+        })
+        .collect()
+}
+
+// Proyecta cada fila del techo sobre el mundo y, donde cae en una celda exterior,
+// muestra el cielo panorámico. El cielo se desplaza con el ángulo del rayo.
+fn render_sky_column(ray: &TracedRay, ceiling_end: usize, ctx: &RenderContext) -> Vec<Option<Color>> {
+    let block_size = 100;
+    let texture_manager = ctx.texture_manager;
+    let hh = ctx.screen_height as f32 / 2.0;
+
+    let Some(sky) = texture_manager.texture(OUTDOOR_CHAR) else {
+        return Vec::new();
+    };
+    let (sky_width, sky_height) = (sky.width(), sky.height());
 
-    // Optimización: reducir rayos a la mitad para mejor rendimiento (cada 2 píxeles)
-    let ray_scale = 2usize;
-    let num_rays = framebuffer.width as usize / ray_scale; 
+    (0..ceiling_end)
+        .map(|y| {
+            let above_horizon = ctx.horizon - (y as f32 + 0.5);
+            if above_horizon <= 0.0 {
+                return None;
+            }
+
+            let (world_x, world_y, segment) = ray.point_at(row_distance(y, ctx.screen_height, ctx.horizon, ctx.eye_height));
+
+            if !is_outdoor_at(ctx.maze, world_x, world_y, block_size) {
+                return None;
+            }
+
+            // El cielo ocupa la media pantalla sobre el horizonte; en los reflejos sigue al rayo reflejado
+            let sky_x = ((segment.angle / std::f32::consts::TAU).rem_euclid(1.0) * sky_width as f32) as u32;
+            let sky_y = (((y as f32 - (ctx.horizon - hh)) / hh).max(0.0) * sky_height as f32) as u32;
+            let color = sky.texel(sky_x, sky_y);
+            Some(tint_reflection(color, segment.bounces))
+        })
+        .collect()
+}
+
+// Sombrea la columna de pared golpeada por el rayo
+fn shade_wall_column(intersect: &Intersect, a: f32, ctx: &RenderContext) -> Option<WallColumn> {
+    let block_size = 100;
+    let screen_height = ctx.screen_height;
+    let texture_manager = ctx.texture_manager;
+    let maze = ctx.maze;
+    let decals = ctx.decals;
+    let light_map = ctx.light_map;
 
-    let _hw = framebuffer.width as f32 / 2.0;
-    let hh = framebuffer.height as f32 / 2.0;
+    let hh = screen_height as f32 / 2.0;
 
     // Constantes para el renderizado
     let distance_to_projection_plane = 100.0;
     let max_distance = 1000.0; // Para normalizar el sombreado
-    
-    // Inicializar z-buffer
-    let mut z_buffer = vec![f32::INFINITY; framebuffer.width as usize];
 
-    for i in 0..num_rays {
-        let current_ray = i as f32 / num_rays as f32;
-        let a = player.a - (player.fov / 2.0) + (player.fov * current_ray);
-        let intersect = cast_ray(framebuffer, &maze, &player, a, block_size, false);
+    // No renderizar nada si el rayo no golpeó una pared (celda vacía o fuera de límites)
+    if is_open_cell(intersect.impact) {
+        return None;
+    }
 
-        // Los triggers ('t', 's', 'c') son transparentes, no renderizar nada si el rayo golpea un trigger
-        // o si no hay pared (espacio vacío o fuera de límites)
-        if intersect.impact == ' ' || intersect.impact == 't' || intersect.impact == 's' || intersect.impact == 'c' {
-            continue;
-        }
+    let distance_to_wall = intersect.distance;
 
-        let distance_to_wall = intersect.distance;
+    // Calcula la intensidad basada en la distancia (1.0 cerca, 0.0 lejos)
+    let intensity = 1.0 - (distance_to_wall / max_distance).min(1.0);
 
-        // Calcula la intensidad basada en la distancia (1.0 cerca, 0.0 lejos)
-        let intensity = 1.0 - (distance_to_wall / max_distance).min(1.0);
+    // Punto justo delante de la pared, en la celda desde donde se la ve
+    let front_x = intersect.hit_x - a.cos() * 2.0;
+    let front_y = intersect.hit_y - a.sin() * 2.0;
 
-        // Calcula la altura del stake con compensación de distancia
-        let stake_height = (hh / distance_to_wall) * distance_to_projection_plane;
+    // Si la celda frente a la pared está al aire libre, la distancia se funde con el color del cielo
+    // en lugar del negro
+    let fog_color = if is_outdoor_at(maze, front_x, front_y, block_size) {
+        SKY_FOG_COLOR
+    } else {
+        Color::BLACK
+    };
 
-        // Posiciones del stake con límites de pantalla
-        let stake_top = ((hh - (stake_height / 2.0)) as usize).max(0);
-        let stake_bottom = ((hh + (stake_height / 2.0)) as usize).min(framebuffer.height as usize);
-        
+    // Luz de las luces puntuales que llega a esta pared
+    let light = light_map.sample(front_x, front_y);
+
+    // Sombreado direccional según el lado de la pared golpeado
+    let side_shade = if intersect.wall_side == 'v' { SIDE_SHADE_V } else { SIDE_SHADE_H };
+
+    // Esquinas interiores a cada extremo de la pared
+    let corners = corner_occlusion(maze, intersect, front_x, front_y, block_size);
+
+    // Calcula la altura del stake con compensación de distancia
+    let stake_height = (hh / distance_to_wall) * distance_to_projection_plane;
+
+    // Posiciones del stake con límites de pantalla
+    // La base de la pared queda `eye_height` por debajo de los ojos y la parte de arriba el resto
+    let unclipped_top = ctx.horizon - stake_height * (1.0 - ctx.eye_height / WALL_HEIGHT);
+    let unclipped_bottom = ctx.horizon + stake_height * (ctx.eye_height / WALL_HEIGHT);
+    let stake_top = (unclipped_top.max(0.0) as usize).min(screen_height);
+    let stake_bottom = (unclipped_bottom.max(0.0) as usize).min(screen_height);
+
+    // Posición del impacto a lo largo de la pared, en [0, 1) (sin el desplazamiento de las puertas)
+    let wall_u = if intersect.wall_side == 'v' {
+        intersect.hit_y.rem_euclid(block_size as f32) / block_size as f32
+    } else {
+        intersect.hit_x.rem_euclid(block_size as f32) / block_size as f32
+    };
+
+    // Cara de la celda golpeada: puede reemplazar la textura de la pared o llevar un decal encima
+    let wall_col = (intersect.hit_x / block_size as f32) as usize;
+    let wall_row = (intersect.hit_y / block_size as f32) as usize;
+    let face = Face::from_hit(intersect.wall_side, a.cos(), a.sin());
+    let texture_char = decals.texture_override(wall_col, wall_row, face).unwrap_or(intersect.impact);
+    let decal = decals.decal(wall_col, wall_row, face).and_then(|decal_char| {
+        let decal_texture = texture_manager.texture(decal_char)?;
+        let decal_u = if face.flips_texture() { 1.0 - wall_u } else { wall_u };
+        Some((decal_texture, decal_u, decal_texture.height() as f32 / stake_height))
+    });
+
+    // Coordenada horizontal de textura; las puertas deslizantes arrastran su textura al abrirse
+    let texture_u = (wall_u - intersect.texture_offset / block_size as f32).max(0.0);
+
+    // Texels de la textura que caen en cada píxel del stake: elige el mipmap
+    let texture = texture_manager.texture(texture_char);
+    let footprint = texture.map_or(1.0, |texture| texture.height() as f32 / stake_height);
+
+    // Sombrea la textura verticalmente
+    let mut colors = Vec::with_capacity(stake_bottom.saturating_sub(stake_top));
+    for y in stake_top..stake_bottom {
+        // Posición vertical sobre la pared, medida sobre el stake completo (sin recortar por la pantalla)
+        let wall_v = ((y as f32 - unclipped_top) / stake_height).clamp(0.0, 1.0);
+
+        // Obtiene el color de la textura
+        let texture_color = texture.map_or(Color::WHITE, |texture| texture.sample(texture_u, wall_v, footprint, ctx.texture_sampling));
+
+        // El decal se mezcla antes del sombreado para que reciba la misma luz que la pared
+        let texture_color = match decal {
+            Some((decal_texture, decal_u, decal_footprint)) => {
+                let decal_color = decal_texture.sample(decal_u, wall_v, decal_footprint, ctx.texture_sampling);
+                blend_decal(texture_color, decal_color)
+            }
+            None => texture_color,
+        };
+
+        // Sombreado por lado y oclusión ambiental
+        let shade = side_shade * ambient_occlusion(wall_u, wall_v, corners);
+        let texture_color = Color::new(
+            (texture_color.r as f32 * shade) as u8,
+            (texture_color.g as f32 * shade) as u8,
+            (texture_color.b as f32 * shade) as u8,
+            texture_color.a,
+        );
+
+        // Aplica la intensidad basada en la distancia
+        let r = (texture_color.r as f32 * intensity + fog_color.r as f32 * (1.0 - intensity)) as u8;
+        let g = (texture_color.g as f32 * intensity + fog_color.g as f32 * (1.0 - intensity)) as u8;
+        let b = (texture_color.b as f32 * intensity + fog_color.b as f32 * (1.0 - intensity)) as u8;
+        colors.push(apply_light(Color::new(r, g, b, 255), light));
+    }
+
+    Some(WallColumn {
+        top: stake_top,
+        colors,
+        distance: distance_to_wall,
+    })
+}
+
+// Lo que render3d necesita para dibujar la vista de un jugador
+pub struct SceneView<'a> {
+    pub player: &'a Player,
+    pub texture_manager: &'a TextureManager,
+    pub maze: &'a [Vec<char>],
+    pub doors: &'a Doors,
+    pub portals: &'a Portals,
+    pub decals: &'a Decals,
+    pub light_map: &'a LightMap,
+    pub ray_scale: usize, // columnas que cubre cada rayo (1 = un rayo por columna)
+    pub texture_sampling: TextureSampling,
+}
+
+// Devuelve el z-buffer y, por columna, el primer espejo visible (para dibujar los sprites reflejados)
+pub fn render3d(framebuffer: &mut Framebuffer, view: &SceneView) -> (Vec<f32>, Vec<Option<Reflection>>) {
+    let player = view.player;
+    let ray_scale = view.ray_scale.max(1);
+    let num_rays = framebuffer.width as usize / ray_scale;
+    let screen_height = framebuffer.height as usize;
+
+    // Inicializar z-buffer
+    let mut z_buffer = vec![f32::INFINITY; framebuffer.width as usize];
+    let mut reflections = vec![None; framebuffer.width as usize];
+
+    // Dividir los rayos en franjas contiguas, una por hilo del pool de render
+    let pool = WorkerPool::global();
+    let num_strips = pool.threads().min(num_rays.max(1));
+    let rays_per_strip = num_rays.div_ceil(num_strips);
+
+    let ctx = RenderContext {
+        player,
+        texture_manager: view.texture_manager,
+        maze: view.maze,
+        doors: view.doors,
+        portals: view.portals,
+        decals: view.decals,
+        light_map: view.light_map,
+        num_rays,
+        screen_height,
+        horizon: player.horizon(screen_height as f32),
+        eye_height: player.eye_height(),
+        texture_sampling: view.texture_sampling,
+    };
+
+    // Las franjas vuelven en orden, así que el resultado es idéntico al de un solo hilo
+    let columns: Vec<RenderedColumn> = pool
+        .map(num_strips, |strip| {
+            let start = (strip * rays_per_strip).min(num_rays);
+            let end = ((strip + 1) * rays_per_strip).min(num_rays);
+            (start..end).map(|i| render_column(i, &ctx)).collect::<Vec<_>>()
+        })
+        .into_iter()
+        .flatten()
+        .collect();
+
+    // Copiar las columnas al framebuffer (el framebuffer solo se toca desde este hilo)
+    for (i, column) in columns.into_iter().enumerate() {
         // Calcular posición X en pantalla (escalada)
         let screen_x = i * ray_scale;
 
-        // Calcular coordenada horizontal de textura (tx)
-        let texture_x = if let Some((tex_width, _)) = texture_manager.get_texture_dimensions(intersect.impact) {
-            let wall_x = if intersect.wall_side == 'v' {
-                // Para paredes verticales, usar la coordenada Y del impacto
-                intersect.hit_y % block_size as f32
-            } else {
-                // Para paredes horizontales, usar la coordenada X del impacto
-                intersect.hit_x % block_size as f32
+        for offset in 0..ray_scale {
+            let x_pos = (screen_x + offset).min(reflections.len() - 1);
+            reflections[x_pos] = column.reflection;
+        }
+
+        // Cielo de las celdas exteriores (el resto del techo ya lo pintó render_world)
+        for (y, color) in column.sky.into_iter().enumerate() {
+            let Some(color) = color else {
+                continue;
             };
-            
-            // Asegurar que la coordenada esté en el rango [0, block_size)
-            let normalized_x = if wall_x < 0.0 {
-                wall_x + block_size as f32
-            } else {
-                wall_x
+            framebuffer.set_current_color(color);
+            for offset in 0..ray_scale {
+                let x_pos = (screen_x + offset).min(framebuffer.width as usize - 1);
+                framebuffer.set_pixel(x_pos as u32, y as u32);
+            }
+        }
+
+        // Piso iluminado por las luces puntuales
+        for (offset_y, color) in column.floor.into_iter().enumerate() {
+            let Some(color) = color else {
+                continue;
             };
-            
-            // Convertir a coordenada de textura
-            let tex_x = (normalized_x / block_size as f32) * tex_width as f32;
-            
-            // Asegurar que esté dentro de los límites y sea un entero válido
-            let final_tex_x = tex_x as u32;
-            if final_tex_x >= tex_width {
-                tex_width - 1
-            } else {
-                final_tex_x
+            framebuffer.set_current_color(color);
+            for offset in 0..ray_scale {
+                let x_pos = (screen_x + offset).min(framebuffer.width as usize - 1);
+                framebuffer.set_pixel(x_pos as u32, (column.floor_top + offset_y) as u32);
             }
-        } else {
-            0
+        }
+
+        let Some(wall) = column.wall else {
+            continue;
         };
 
-        // Renderiza la textura verticalmente
-        for y in stake_top..stake_bottom {
-            // Calcula la coordenada de textura Y (ty)
-            let texture_y = if let Some((_, tex_height)) = texture_manager.get_texture_dimensions(intersect.impact) {
-                let relative_y = (y - stake_top) as f32 / (stake_bottom - stake_top) as f32;
-                (relative_y * tex_height as f32) as u32
-            } else {
-                ((y - stake_top) as f32 / (stake_bottom - stake_top) as f32 * 63.0) as u32
-            };
-            
-            // Obtiene el color de la textura
-            let texture_color = texture_manager.get_pixel_color(intersect.impact, texture_x, texture_y);
-            
-            // Aplica la intensidad basada en la distancia
-            let r = (texture_color.r as f32 * intensity) as u8;
-            let g = (texture_color.g as f32 * intensity) as u8;
-            let b = (texture_color.b as f32 * intensity) as u8;
-            let final_color = Color::new(r, g, b, 255);
-            
-            framebuffer.set_current_color(final_color);
+        for (offset_y, color) in wall.colors.into_iter().enumerate() {
+            framebuffer.set_current_color(color);
             // Dibujar en múltiples columnas para compensar la reducción de rayos
             for offset in 0..ray_scale {
                 let x_pos = (screen_x + offset).min(framebuffer.width as usize - 1);
-                framebuffer.set_pixel(x_pos as u32, y as u32);
+                framebuffer.set_pixel(x_pos as u32, (wall.top + offset_y) as u32);
             }
         }
-        
+
         // Actualizar z-buffer para todas las columnas de este rayo
         for offset in 0..ray_scale {
             let x_pos = (screen_x + offset).min(z_buffer.len() - 1);
-            z_buffer[x_pos] = distance_to_wall;
+            z_buffer[x_pos] = wall.distance;
         }
     }
-    
-    z_buffer
+
+    (z_buffer, reflections)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn half_open_door(horizontal: bool) -> Door {
+        Door { col: 1, row: 1, horizontal, open_amount: 0.5, opening: true }
+    }
+
+    #[test]
+    fn horizontal_door_slides_toward_positive_x() {
+        let door = half_open_door(true);
+        // Rayos hacia el sur: la mitad izquierda de la celda quedó libre y la hoja está a la derecha
+        assert!(intersect_door(&door, 120.0, 0.0, 0.0, 1.0, 100).is_none());
+        let hit = intersect_door(&door, 180.0, 0.0, 0.0, 1.0, 100).unwrap();
+        assert_eq!(hit.hit_y, 150.0);
+        assert_eq!(hit.texture_offset, 50.0);
+    }
+
+    #[test]
+    fn vertical_door_slides_toward_positive_y() {
+        let door = half_open_door(false);
+        // Rayos hacia el este: la mitad de arriba quedó libre y la hoja está abajo
+        assert!(intersect_door(&door, 0.0, 120.0, 1.0, 0.0, 100).is_none());
+        let hit = intersect_door(&door, 0.0, 180.0, 1.0, 0.0, 100).unwrap();
+        assert_eq!(hit.hit_x, 150.0);
+    }
 }
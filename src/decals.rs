@@ -0,0 +1,115 @@
+// decals.rs - Decals y texturas por cara de pared
+//
+// Se declaran en el archivo del laberinto con líneas que empiezan con '#', después de la grilla:
+//   #decal <columna> <fila> <cara N|S|E|W> <textura>   dibuja la textura encima de la pared
+//   #face  <columna> <fila> <cara N|S|E|W> <textura>   reemplaza la textura de esa cara
+// <textura> es el carácter con el que la textura está registrada en el TextureManager
+
+use raylib::color::Color;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+
+// Cara de una celda de pared (el norte es la fila de arriba, y crece hacia abajo)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Face {
+    North,
+    South,
+    East,
+    West,
+}
+
+impl Face {
+    fn parse(text: &str) -> Option<Face> {
+        match text {
+            "N" | "n" => Some(Face::North),
+            "S" | "s" => Some(Face::South),
+            "E" | "e" => Some(Face::East),
+            "W" | "w" | "O" | "o" => Some(Face::West),
+            _ => None,
+        }
+    }
+
+    // Cara golpeada por un rayo según el lado de la pared ('h'/'v') y su dirección
+    pub fn from_hit(wall_side: char, cos_a: f32, sin_a: f32) -> Face {
+        if wall_side == 'v' {
+            if cos_a > 0.0 { Face::West } else { Face::East }
+        } else if sin_a > 0.0 {
+            Face::North
+        } else {
+            Face::South
+        }
+    }
+
+    // Desde estas caras la coordenada de textura crece hacia la izquierda de la pantalla;
+    // los decals se invierten para que el texto y las flechas se lean bien
+    pub fn flips_texture(&self) -> bool {
+        matches!(self, Face::East | Face::North)
+    }
+}
+
+pub struct Decals {
+    decals: HashMap<(usize, usize, Face), char>,
+    overrides: HashMap<(usize, usize, Face), char>,
+}
+
+impl Decals {
+    pub fn empty() -> Self {
+        Decals {
+            decals: HashMap::new(),
+            overrides: HashMap::new(),
+        }
+    }
+
+    // Leer las líneas '#decal' y '#face' del archivo del laberinto
+    // Las líneas mal formadas se ignoran
+    pub fn load(filename: &str) -> Self {
+        let mut decals = Decals::empty();
+        let Ok(file) = File::open(filename) else {
+            return decals;
+        };
+
+        for line in BufReader::new(file).lines().map_while(Result::ok) {
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            let [kind, col, row, face, texture] = parts.as_slice() else {
+                continue;
+            };
+            let (Ok(col), Ok(row), Some(face), Some(texture)) =
+                (col.parse::<usize>(), row.parse::<usize>(), Face::parse(face), texture.chars().next())
+            else {
+                continue;
+            };
+
+            match *kind {
+                "#decal" => {
+                    decals.decals.insert((col, row, face), texture);
+                }
+                "#face" => {
+                    decals.overrides.insert((col, row, face), texture);
+                }
+                _ => {}
+            }
+        }
+
+        decals
+    }
+
+    pub fn decal(&self, col: usize, row: usize, face: Face) -> Option<char> {
+        self.decals.get(&(col, row, face)).copied()
+    }
+
+    pub fn texture_override(&self, col: usize, row: usize, face: Face) -> Option<char> {
+        self.overrides.get(&(col, row, face)).copied()
+    }
+}
+
+// Mezclar un texel de decal sobre el color de la pared según su alpha
+pub fn blend_decal(base: Color, decal: Color) -> Color {
+    let alpha = decal.a as f32 / 255.0;
+    Color::new(
+        (base.r as f32 * (1.0 - alpha) + decal.r as f32 * alpha) as u8,
+        (base.g as f32 * (1.0 - alpha) + decal.g as f32 * alpha) as u8,
+        (base.b as f32 * (1.0 - alpha) + decal.b as f32 * alpha) as u8,
+        base.a,
+    )
+}
@@ -0,0 +1,315 @@
+// font.rs - Fuentes de mapa de bits para dibujar texto dentro del framebuffer
+//
+// Hay una fuente incorporada de 5x7 píxeles (con descendentes y las letras del español) y se
+// pueden cargar fuentes de consola PSF (versiones 1 y 2). El texto se dibuja con
+// `Framebuffer::draw_text`, así que sale igual con o sin ventana.
+
+use raylib::color::Color;
+use std::collections::HashMap;
+use std::fs;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TextAlign {
+    Left,   // `x` es el borde izquierdo del texto
+    Center, // `x` es el centro del texto
+    Right,  // `x` es el borde derecho del texto
+}
+
+// Cómo se dibuja un texto: cada píxel de la fuente ocupa `scale` x `scale` píxeles
+#[derive(Debug, Clone, Copy)]
+pub struct TextStyle {
+    pub scale: u32,
+    pub color: Color,
+    pub align: TextAlign,
+    pub outline: Option<Color>, // contorno de un píxel (escalado) alrededor de cada letra
+}
+
+impl TextStyle {
+    pub fn new(scale: u32, color: Color) -> Self {
+        TextStyle {
+            scale: scale.max(1),
+            color,
+            align: TextAlign::Left,
+            outline: None,
+        }
+    }
+
+    pub fn aligned(mut self, align: TextAlign) -> Self {
+        self.align = align;
+        self
+    }
+
+    pub fn outlined(mut self, color: Color) -> Self {
+        self.outline = Some(color);
+        self
+    }
+}
+
+pub struct BitmapFont {
+    pub glyph_width: u32,
+    pub glyph_height: u32,
+    // Filas de cada letra; el bit `glyph_width - 1 - x` encendido es el píxel de la columna x
+    glyphs: HashMap<char, Vec<u32>>,
+}
+
+// Letra que se dibuja en lugar de los caracteres que la fuente no tiene
+const FALLBACK_GLYPH: char = '?';
+
+impl BitmapFont {
+    // Fuente incorporada: 5 columnas y 9 filas (línea base en la fila 6, descendentes debajo)
+    pub fn builtin() -> Self {
+        let glyphs = BUILTIN_GLYPHS
+            .iter()
+            .map(|(ch, rows)| (*ch, rows.iter().map(|&row| row as u32).collect()))
+            .collect();
+        BitmapFont {
+            glyph_width: 5,
+            glyph_height: 9,
+            glyphs,
+        }
+    }
+
+    // Cargar una fuente de consola PSF1 o PSF2; devuelve None si el archivo no es válido
+    pub fn load_psf(path: &str) -> Option<Self> {
+        let data = fs::read(path).ok()?;
+        let read_u32 = |offset: usize| -> Option<u32> {
+            Some(u32::from_le_bytes(data.get(offset..offset + 4)?.try_into().ok()?))
+        };
+
+        // (ancho, alto, cantidad de letras, bytes por letra, inicio de las letras, tabla unicode)
+        let (width, height, count, glyph_size, start, has_table) = if data.starts_with(&[0x36, 0x04]) {
+            let mode = *data.get(2)?;
+            let height = *data.get(3)? as u32;
+            let count = if mode & 0x01 != 0 { 512 } else { 256 };
+            (8, height, count, height as usize, 4, mode & 0x06 != 0)
+        } else if data.starts_with(&[0x72, 0xb5, 0x4a, 0x86]) {
+            let header_size = read_u32(8)? as usize;
+            let flags = read_u32(12)?;
+            let count = read_u32(16)? as usize;
+            let glyph_size = read_u32(20)? as usize;
+            (read_u32(28)?, read_u32(24)?, count, glyph_size, header_size, flags & 0x01 != 0)
+        } else {
+            return None;
+        };
+
+        let bytes_per_row = width.div_ceil(8) as usize;
+        let table_start = start + count * glyph_size;
+        let bitmaps = data.get(start..table_start)?;
+
+        // Caracteres de cada letra: los de la tabla unicode, o el propio índice si no hay tabla
+        let chars: Vec<Vec<char>> = if has_table {
+            parse_psf_table(&data[table_start..], count, data[0] == 0x36)
+        } else {
+            (0..count).map(|index| char::from_u32(index as u32).into_iter().collect()).collect()
+        };
+
+        let mut glyphs = HashMap::new();
+        for (bitmap, chars) in bitmaps.chunks_exact(glyph_size).zip(chars) {
+            let rows: Vec<u32> = bitmap
+                .chunks_exact(bytes_per_row)
+                .take(height as usize)
+                .map(|row| {
+                    // Los bits vienen de izquierda a derecha empezando por el más significativo
+                    let bits = row.iter().fold(0u32, |acc, &byte| (acc << 8) | byte as u32);
+                    bits >> (bytes_per_row as u32 * 8 - width)
+                })
+                .collect();
+            for ch in chars {
+                glyphs.entry(ch).or_insert_with(|| rows.clone());
+            }
+        }
+
+        Some(BitmapFont {
+            glyph_width: width,
+            glyph_height: height,
+            glyphs,
+        })
+    }
+
+    // Filas de la letra de un carácter (o de '?' si la fuente no lo tiene)
+    pub fn glyph(&self, ch: char) -> Option<&[u32]> {
+        self.glyphs
+            .get(&ch)
+            .or_else(|| self.glyphs.get(&FALLBACK_GLYPH))
+            .map(Vec::as_slice)
+    }
+
+    // Avance horizontal de cada letra (incluye un píxel de separación)
+    pub fn advance(&self, scale: u32) -> u32 {
+        (self.glyph_width + 1) * scale
+    }
+
+    pub fn line_height(&self, scale: u32) -> u32 {
+        (self.glyph_height + 1) * scale
+    }
+
+    // Ancho en píxeles de una línea de texto
+    pub fn text_width(&self, text: &str, scale: u32) -> u32 {
+        let count = text.chars().count() as u32;
+        (count * self.advance(scale)).saturating_sub(scale)
+    }
+}
+
+// Leer la tabla unicode de una fuente PSF: una lista de caracteres por letra
+// En PSF1 son u16 terminados en 0xFFFF; en PSF2 son UTF-8 terminados en 0xFF.
+// Las secuencias (después de 0xFFFE / 0xFE) combinan varios caracteres y se ignoran
+fn parse_psf_table(table: &[u8], count: usize, psf1: bool) -> Vec<Vec<char>> {
+    let mut chars = vec![Vec::new(); count];
+    let mut index = 0;
+    let mut in_sequence = false;
+
+    if psf1 {
+        for pair in table.chunks_exact(2) {
+            if index >= count {
+                break;
+            }
+            match u16::from_le_bytes([pair[0], pair[1]]) {
+                0xFFFF => {
+                    index += 1;
+                    in_sequence = false;
+                }
+                0xFFFE => in_sequence = true,
+                code if !in_sequence => chars[index].extend(char::from_u32(code as u32)),
+                _ => {}
+            }
+        }
+    } else {
+        let mut entry = Vec::new();
+        for &byte in table {
+            if index >= count {
+                break;
+            }
+            match byte {
+                0xFF | 0xFE => {
+                    if !in_sequence {
+                        chars[index].extend(String::from_utf8_lossy(&entry).chars());
+                    }
+                    entry.clear();
+                    if byte == 0xFF {
+                        index += 1;
+                        in_sequence = false;
+                    } else {
+                        in_sequence = true;
+                    }
+                }
+                _ => entry.push(byte),
+            }
+        }
+    }
+
+    chars
+}
+
+// Fuente incorporada: una fila por elemento, el bit 4 es la columna de la izquierda
+const BUILTIN_GLYPHS: &[(char, [u8; 9])] = &[
+    (' ', [0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000]),
+    ('!', [0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00000, 0b00100, 0b00000, 0b00000]),
+    ('"', [0b01010, 0b01010, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000]),
+    ('#', [0b01010, 0b01010, 0b11111, 0b01010, 0b11111, 0b01010, 0b01010, 0b00000, 0b00000]),
+    ('$', [0b00100, 0b01111, 0b10100, 0b01110, 0b00101, 0b11110, 0b00100, 0b00000, 0b00000]),
+    ('%', [0b11000, 0b11001, 0b00010, 0b00100, 0b01000, 0b10011, 0b00011, 0b00000, 0b00000]),
+    ('&', [0b01100, 0b10010, 0b10100, 0b01000, 0b10101, 0b10010, 0b01101, 0b00000, 0b00000]),
+    ('\'', [0b00100, 0b00100, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000]),
+    ('(', [0b00010, 0b00100, 0b01000, 0b01000, 0b01000, 0b00100, 0b00010, 0b00000, 0b00000]),
+    (')', [0b01000, 0b00100, 0b00010, 0b00010, 0b00010, 0b00100, 0b01000, 0b00000, 0b00000]),
+    ('*', [0b00000, 0b00100, 0b10101, 0b01110, 0b10101, 0b00100, 0b00000, 0b00000, 0b00000]),
+    ('+', [0b00000, 0b00100, 0b00100, 0b11111, 0b00100, 0b00100, 0b00000, 0b00000, 0b00000]),
+    (',', [0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00100, 0b00100, 0b01000, 0b00000]),
+    ('-', [0b00000, 0b00000, 0b00000, 0b11111, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000]),
+    ('.', [0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b01100, 0b01100, 0b00000, 0b00000]),
+    ('/', [0b00000, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0b00000, 0b00000, 0b00000]),
+    ('0', [0b01110, 0b10001, 0b10011, 0b10101, 0b11001, 0b10001, 0b01110, 0b00000, 0b00000]),
+    ('1', [0b00100, 0b01100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110, 0b00000, 0b00000]),
+    ('2', [0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b01000, 0b11111, 0b00000, 0b00000]),
+    ('3', [0b11111, 0b00010, 0b00100, 0b00010, 0b00001, 0b10001, 0b01110, 0b00000, 0b00000]),
+    ('4', [0b00010, 0b00110, 0b01010, 0b10010, 0b11111, 0b00010, 0b00010, 0b00000, 0b00000]),
+    ('5', [0b11111, 0b10000, 0b11110, 0b00001, 0b00001, 0b10001, 0b01110, 0b00000, 0b00000]),
+    ('6', [0b00110, 0b01000, 0b10000, 0b11110, 0b10001, 0b10001, 0b01110, 0b00000, 0b00000]),
+    ('7', [0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b01000, 0b01000, 0b00000, 0b00000]),
+    ('8', [0b01110, 0b10001, 0b10001, 0b01110, 0b10001, 0b10001, 0b01110, 0b00000, 0b00000]),
+    ('9', [0b01110, 0b10001, 0b10001, 0b01111, 0b00001, 0b00010, 0b01100, 0b00000, 0b00000]),
+    (':', [0b00000, 0b01100, 0b01100, 0b00000, 0b01100, 0b01100, 0b00000, 0b00000, 0b00000]),
+    (';', [0b00000, 0b01100, 0b01100, 0b00000, 0b01100, 0b01100, 0b00100, 0b01000, 0b00000]),
+    ('<', [0b00010, 0b00100, 0b01000, 0b10000, 0b01000, 0b00100, 0b00010, 0b00000, 0b00000]),
+    ('=', [0b00000, 0b00000, 0b11111, 0b00000, 0b11111, 0b00000, 0b00000, 0b00000, 0b00000]),
+    ('>', [0b01000, 0b00100, 0b00010, 0b00001, 0b00010, 0b00100, 0b01000, 0b00000, 0b00000]),
+    ('?', [0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b00000, 0b00100, 0b00000, 0b00000]),
+    ('@', [0b01110, 0b10001, 0b00001, 0b01101, 0b10101, 0b10101, 0b01110, 0b00000, 0b00000]),
+    ('A', [0b01110, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001, 0b00000, 0b00000]),
+    ('B', [0b11110, 0b10001, 0b10001, 0b11110, 0b10001, 0b10001, 0b11110, 0b00000, 0b00000]),
+    ('C', [0b01110, 0b10001, 0b10000, 0b10000, 0b10000, 0b10001, 0b01110, 0b00000, 0b00000]),
+    ('D', [0b11100, 0b10010, 0b10001, 0b10001, 0b10001, 0b10010, 0b11100, 0b00000, 0b00000]),
+    ('E', [0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b11111, 0b00000, 0b00000]),
+    ('F', [0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b10000, 0b00000, 0b00000]),
+    ('G', [0b01110, 0b10001, 0b10000, 0b10111, 0b10001, 0b10001, 0b01111, 0b00000, 0b00000]),
+    ('H', [0b10001, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001, 0b00000, 0b00000]),
+    ('I', [0b01110, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110, 0b00000, 0b00000]),
+    ('J', [0b00111, 0b00010, 0b00010, 0b00010, 0b00010, 0b10010, 0b01100, 0b00000, 0b00000]),
+    ('K', [0b10001, 0b10010, 0b10100, 0b11000, 0b10100, 0b10010, 0b10001, 0b00000, 0b00000]),
+    ('L', [0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b11111, 0b00000, 0b00000]),
+    ('M', [0b10001, 0b11011, 0b10101, 0b10101, 0b10001, 0b10001, 0b10001, 0b00000, 0b00000]),
+    ('N', [0b10001, 0b10001, 0b11001, 0b10101, 0b10011, 0b10001, 0b10001, 0b00000, 0b00000]),
+    ('O', [0b01110, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110, 0b00000, 0b00000]),
+    ('P', [0b11110, 0b10001, 0b10001, 0b11110, 0b10000, 0b10000, 0b10000, 0b00000, 0b00000]),
+    ('Q', [0b01110, 0b10001, 0b10001, 0b10001, 0b10101, 0b10010, 0b01101, 0b00000, 0b00000]),
+    ('R', [0b11110, 0b10001, 0b10001, 0b11110, 0b10100, 0b10010, 0b10001, 0b00000, 0b00000]),
+    ('S', [0b01111, 0b10000, 0b10000, 0b01110, 0b00001, 0b00001, 0b11110, 0b00000, 0b00000]),
+    ('T', [0b11111, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00000, 0b00000]),
+    ('U', [0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110, 0b00000, 0b00000]),
+    ('V', [0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01010, 0b00100, 0b00000, 0b00000]),
+    ('W', [0b10001, 0b10001, 0b10001, 0b10101, 0b10101, 0b10101, 0b01010, 0b00000, 0b00000]),
+    ('X', [0b10001, 0b10001, 0b01010, 0b00100, 0b01010, 0b10001, 0b10001, 0b00000, 0b00000]),
+    ('Y', [0b10001, 0b10001, 0b01010, 0b00100, 0b00100, 0b00100, 0b00100, 0b00000, 0b00000]),
+    ('Z', [0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0b11111, 0b00000, 0b00000]),
+    ('[', [0b01110, 0b01000, 0b01000, 0b01000, 0b01000, 0b01000, 0b01110, 0b00000, 0b00000]),
+    ('\\', [0b00000, 0b10000, 0b01000, 0b00100, 0b00010, 0b00001, 0b00000, 0b00000, 0b00000]),
+    (']', [0b01110, 0b00010, 0b00010, 0b00010, 0b00010, 0b00010, 0b01110, 0b00000, 0b00000]),
+    ('^', [0b00100, 0b01010, 0b10001, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000]),
+    ('_', [0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b11111, 0b00000, 0b00000]),
+    ('`', [0b01000, 0b00100, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000]),
+    ('a', [0b00000, 0b00000, 0b01110, 0b00001, 0b01111, 0b10001, 0b01111, 0b00000, 0b00000]),
+    ('b', [0b10000, 0b10000, 0b10110, 0b11001, 0b10001, 0b10001, 0b11110, 0b00000, 0b00000]),
+    ('c', [0b00000, 0b00000, 0b01110, 0b10000, 0b10000, 0b10001, 0b01110, 0b00000, 0b00000]),
+    ('d', [0b00001, 0b00001, 0b01101, 0b10011, 0b10001, 0b10001, 0b01111, 0b00000, 0b00000]),
+    ('e', [0b00000, 0b00000, 0b01110, 0b10001, 0b11111, 0b10000, 0b01110, 0b00000, 0b00000]),
+    ('f', [0b00110, 0b01001, 0b01000, 0b11100, 0b01000, 0b01000, 0b01000, 0b00000, 0b00000]),
+    ('g', [0b00000, 0b00000, 0b01111, 0b10001, 0b10001, 0b01111, 0b00001, 0b10001, 0b01110]),
+    ('h', [0b10000, 0b10000, 0b10110, 0b11001, 0b10001, 0b10001, 0b10001, 0b00000, 0b00000]),
+    ('i', [0b00100, 0b00000, 0b01100, 0b00100, 0b00100, 0b00100, 0b01110, 0b00000, 0b00000]),
+    ('j', [0b00010, 0b00000, 0b00110, 0b00010, 0b00010, 0b00010, 0b00010, 0b10010, 0b01100]),
+    ('k', [0b10000, 0b10000, 0b10010, 0b10100, 0b11000, 0b10100, 0b10010, 0b00000, 0b00000]),
+    ('l', [0b01100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110, 0b00000, 0b00000]),
+    ('m', [0b00000, 0b00000, 0b11010, 0b10101, 0b10101, 0b10001, 0b10001, 0b00000, 0b00000]),
+    ('n', [0b00000, 0b00000, 0b10110, 0b11001, 0b10001, 0b10001, 0b10001, 0b00000, 0b00000]),
+    ('o', [0b00000, 0b00000, 0b01110, 0b10001, 0b10001, 0b10001, 0b01110, 0b00000, 0b00000]),
+    ('p', [0b00000, 0b00000, 0b11110, 0b10001, 0b10001, 0b11110, 0b10000, 0b10000, 0b10000]),
+    ('q', [0b00000, 0b00000, 0b01111, 0b10001, 0b10001, 0b01111, 0b00001, 0b00001, 0b00001]),
+    ('r', [0b00000, 0b00000, 0b10110, 0b11001, 0b10000, 0b10000, 0b10000, 0b00000, 0b00000]),
+    ('s', [0b00000, 0b00000, 0b01111, 0b10000, 0b01110, 0b00001, 0b11110, 0b00000, 0b00000]),
+    ('t', [0b01000, 0b01000, 0b11100, 0b01000, 0b01000, 0b01001, 0b00110, 0b00000, 0b00000]),
+    ('u', [0b00000, 0b00000, 0b10001, 0b10001, 0b10001, 0b10011, 0b01101, 0b00000, 0b00000]),
+    ('v', [0b00000, 0b00000, 0b10001, 0b10001, 0b10001, 0b01010, 0b00100, 0b00000, 0b00000]),
+    ('w', [0b00000, 0b00000, 0b10001, 0b10001, 0b10101, 0b10101, 0b01010, 0b00000, 0b00000]),
+    ('x', [0b00000, 0b00000, 0b10001, 0b01010, 0b00100, 0b01010, 0b10001, 0b00000, 0b00000]),
+    ('y', [0b00000, 0b00000, 0b10001, 0b10001, 0b10001, 0b01111, 0b00001, 0b10001, 0b01110]),
+    ('z', [0b00000, 0b00000, 0b11111, 0b00010, 0b00100, 0b01000, 0b11111, 0b00000, 0b00000]),
+    ('{', [0b00010, 0b00100, 0b00100, 0b01000, 0b00100, 0b00100, 0b00010, 0b00000, 0b00000]),
+    ('|', [0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00000, 0b00000]),
+    ('}', [0b01000, 0b00100, 0b00100, 0b00010, 0b00100, 0b00100, 0b01000, 0b00000, 0b00000]),
+    ('~', [0b00000, 0b00000, 0b01000, 0b10101, 0b00010, 0b00000, 0b00000, 0b00000, 0b00000]),
+    ('á', [0b00010, 0b00100, 0b01110, 0b00001, 0b01111, 0b10001, 0b01111, 0b00000, 0b00000]),
+    ('é', [0b00010, 0b00100, 0b01110, 0b10001, 0b11111, 0b10000, 0b01110, 0b00000, 0b00000]),
+    ('í', [0b00010, 0b00100, 0b01100, 0b00100, 0b00100, 0b00100, 0b01110, 0b00000, 0b00000]),
+    ('ó', [0b00010, 0b00100, 0b01110, 0b10001, 0b10001, 0b10001, 0b01110, 0b00000, 0b00000]),
+    ('ú', [0b00010, 0b00100, 0b10001, 0b10001, 0b10001, 0b10011, 0b01101, 0b00000, 0b00000]),
+    ('ü', [0b01010, 0b00000, 0b10001, 0b10001, 0b10001, 0b10011, 0b01101, 0b00000, 0b00000]),
+    ('ñ', [0b01101, 0b10010, 0b10110, 0b11001, 0b10001, 0b10001, 0b10001, 0b00000, 0b00000]),
+    ('Á', [0b00010, 0b00100, 0b01110, 0b10001, 0b11111, 0b10001, 0b10001, 0b00000, 0b00000]),
+    ('É', [0b00010, 0b00100, 0b11111, 0b10000, 0b11110, 0b10000, 0b11111, 0b00000, 0b00000]),
+    ('Í', [0b00010, 0b00100, 0b01110, 0b00100, 0b00100, 0b00100, 0b01110, 0b00000, 0b00000]),
+    ('Ó', [0b00010, 0b00100, 0b01110, 0b10001, 0b10001, 0b10001, 0b01110, 0b00000, 0b00000]),
+    ('Ú', [0b00010, 0b00100, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110, 0b00000, 0b00000]),
+    ('Ñ', [0b01101, 0b10010, 0b10001, 0b11001, 0b10101, 0b10011, 0b10001, 0b00000, 0b00000]),
+    ('¡', [0b00100, 0b00000, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00000, 0b00000]),
+    ('¿', [0b00100, 0b00000, 0b00100, 0b01000, 0b10000, 0b10001, 0b01110, 0b00000, 0b00000]),
+];
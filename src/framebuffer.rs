@@ -1,16 +1,64 @@
 use raylib::prelude::*;
 
+use crate::font::{BitmapFont, TextAlign, TextStyle};
+use crate::worker_pool::WorkerPool;
+
+// Filtro usado al escalar un framebuffer a otro de distinto tamaño
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UpscaleFilter {
+    Nearest,
+    Bilinear,
+}
+
+// Cómo se combina un color con el píxel que ya está en el framebuffer
+// El alpha del color dice cuánto del efecto se aplica (255 = completo)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BlendMode {
+    Alpha,    // mezclar con el color de fondo
+    Additive, // sumar el color (brillos, destellos)
+    Multiply, // multiplicar por el color (oscurecer, teñir)
+}
+
+// Mezclar `src` sobre `dst` según el modo; el alpha del resultado es el de `dst`
+pub fn blend_colors(dst: Color, src: Color, mode: BlendMode) -> Color {
+    let alpha = src.a as f32 / 255.0;
+    let blend = |d: u8, s: u8| -> u8 {
+        let (d, s) = (d as f32, s as f32);
+        let value = match mode {
+            BlendMode::Alpha => d + (s - d) * alpha,
+            BlendMode::Additive => d + s * alpha,
+            BlendMode::Multiply => d + (d * s / 255.0 - d) * alpha,
+        };
+        value.clamp(0.0, 255.0) as u8
+    };
+    Color::new(blend(dst.r, src.r), blend(dst.g, src.g), blend(dst.b, src.b), dst.a)
+}
+
 pub struct Framebuffer {
     pub width: u32,
     pub height: u32,
     pub background_color: Color,
     pub current_color: Color,
-    pub color_buffer: Image,
+    color_buffer: Vec<u32>,     // píxeles RGBA empaquetados, fila por fila
+    texture: Option<Texture2D>, // textura de la ventana, se crea una vez y se actualiza cada frame
+}
+
+// Empaquetar un color en un u32 cuyos bytes en memoria quedan en orden R, G, B, A
+// (el mismo orden que espera una textura PIXELFORMAT_UNCOMPRESSED_R8G8B8A8)
+#[inline]
+pub fn pack_color(color: Color) -> u32 {
+    u32::from_ne_bytes([color.r, color.g, color.b, color.a])
+}
+
+#[inline]
+pub fn unpack_color(pixel: u32) -> Color {
+    let [r, g, b, a] = pixel.to_ne_bytes();
+    Color::new(r, g, b, a)
 }
 
 impl Framebuffer {
     pub fn new(width: u32, height: u32, background_color: Color) -> Self {
-        let color_buffer = Image::gen_image_color(width as i32, height as i32, background_color);
+        let color_buffer = vec![pack_color(background_color); (width * height) as usize];
 
         Framebuffer {
             width,
@@ -18,18 +66,17 @@ impl Framebuffer {
             background_color,
             current_color: Color::WHITE,
             color_buffer,
+            texture: None,
         }
     }
     //limpiar el framebuffer
     pub fn clear(&mut self) {
-        self.color_buffer =
-            Image::gen_image_color(self.width as i32, self.height as i32, self.background_color);
+        self.color_buffer.fill(pack_color(self.background_color));
     }
     //poner píxel en la pantalla
     pub fn set_pixel(&mut self, x: u32, y: u32) {
         if x < self.width && y < self.height {
-            self.color_buffer
-                .draw_pixel(x as i32, y as i32, self.current_color);
+            self.color_buffer[(y * self.width + x) as usize] = pack_color(self.current_color);
         }
     }
     //setear el color de fondo
@@ -40,44 +87,92 @@ impl Framebuffer {
     pub fn set_current_color(&mut self, color: Color) {
         self.current_color = color;
     }
+    // Acceso directo a los píxeles empaquetados (ver `pack_color`), fila por fila
+    pub fn pixels(&self) -> &[u32] {
+        &self.color_buffer
+    }
+    pub fn pixels_mut(&mut self) -> &mut [u32] {
+        &mut self.color_buffer
+    }
+    // Una fila de píxeles
+    pub fn row_mut(&mut self, y: u32) -> &mut [u32] {
+        let start = (y * self.width) as usize;
+        &mut self.color_buffer[start..start + self.width as usize]
+    }
+    // Llamar a `f(y, fila)` para cada fila, repartiendo franjas contiguas de filas entre los
+    // hilos del pool de render (cada hilo solo toca sus propias filas)
+    pub fn par_rows(&mut self, f: impl Fn(u32, &mut [u32]) + Sync) {
+        let pool = WorkerPool::global();
+        let width = self.width.max(1) as usize;
+        let num_strips = pool.threads().min(self.height.max(1) as usize);
+        let rows_per_strip = (self.height as usize).div_ceil(num_strips).max(1);
+
+        let mut strips: Vec<&mut [u32]> = self.color_buffer.chunks_mut(rows_per_strip * width).collect();
+        pool.for_each_mut(&mut strips, |strip_index, strip| {
+            for (row_index, row) in strip.chunks_mut(width).enumerate() {
+                f((strip_index * rows_per_strip + row_index) as u32, row);
+            }
+        });
+    }
+    // Los píxeles como bytes R, G, B, A consecutivos
+    fn as_bytes(&self) -> &[u8] {
+        // SAFETY: un u32 ocupa 4 bytes sin relleno y u8 no tiene requisitos de alineación
+        unsafe { std::slice::from_raw_parts(self.color_buffer.as_ptr() as *const u8, self.color_buffer.len() * 4) }
+    }
     //guardar el framebuffer en un archivo usando export
     pub fn render_to_file(&self, file_path: &str) {
-        self.color_buffer.export_image(file_path);
+        let image = Image::gen_image_color(self.width as i32, self.height as i32, Color::BLACK);
+        let bytes = self.as_bytes();
+        // SAFETY: gen_image_color crea una imagen R8G8B8A8 de width * height píxeles,
+        // exactamente el tamaño de `bytes`
+        unsafe {
+            std::ptr::copy_nonoverlapping(bytes.as_ptr(), image.data as *mut u8, bytes.len());
+        }
+        image.export_image(file_path);
+    }
+    //cargar un framebuffer desde una imagen en disco (por ejemplo, una imagen de referencia)
+    pub fn load_from_file(file_path: &str) -> Option<Self> {
+        Image::load_image(file_path).ok().map(|image| Framebuffer::from_image(&image))
+    }
+    //copiar los píxeles de una imagen de raylib a un framebuffer nuevo
+    pub fn from_image(image: &Image) -> Self {
+        let mut framebuffer = Framebuffer::new(image.width as u32, image.height as u32, Color::BLACK);
+        for (pixel, color) in framebuffer.color_buffer.iter_mut().zip(image.get_image_data().iter()) {
+            *pixel = pack_color(*color);
+        }
+        framebuffer
     }
-    pub fn swap_buffer(&self, window: &mut RaylibHandle, raylib_thread: &RaylibThread) {
-        // Cargar textura una sola vez
-        if let Ok(texture) = window.load_texture_from_image(raylib_thread, &self.color_buffer) {
+    // Subir los píxeles a la textura de la ventana y dibujarla; `overlay` dibuja encima
+    // (texto de raylib, por ejemplo). La textura se crea la primera vez y luego solo se actualiza
+    pub fn present(&mut self, window: &mut RaylibHandle, raylib_thread: &RaylibThread, overlay: impl FnOnce(&mut RaylibDrawHandle)) {
+        let size_changed = self
+            .texture
+            .as_ref()
+            .is_some_and(|texture| texture.width as u32 != self.width || texture.height as u32 != self.height);
+        if self.texture.is_none() || size_changed {
+            let image = Image::gen_image_color(self.width as i32, self.height as i32, Color::BLACK);
+            self.texture = window.load_texture_from_image(raylib_thread, &image).ok();
+        }
+
+        let Some(mut texture) = self.texture.take() else {
+            return;
+        };
+        if texture.update_texture(self.as_bytes()).is_ok() {
             let mut render = window.begin_drawing(raylib_thread);
             render.clear_background(Color::BLACK);
             render.draw_texture(&texture, 0, 0, Color::WHITE);
+            overlay(&mut render);
             // El render se cierra automáticamente al salir del scope
         }
+        self.texture = Some(texture);
+    }
+    pub fn swap_buffer(&mut self, window: &mut RaylibHandle, raylib_thread: &RaylibThread) {
+        self.present(window, raylib_thread, |_| {});
     }
     // Obtener el color de un píxel específico
     pub fn get_pixel(&self, x: u32, y: u32) -> Color {
         if x < self.width && y < self.height {
-            unsafe {
-                let raylib_color = raylib::ffi::GetImageColor(
-                    raylib::ffi::Image {
-                        data: self.color_buffer.data,
-                        width: self.color_buffer.width,
-                        height: self.color_buffer.height,
-                        mipmaps: self.color_buffer.mipmaps,
-                        format: self.color_buffer.format,
-                    },
-                    x as i32,
-                    y as i32
-                );
-                
-                // Manejar diferentes formatos de imagen
-                let alpha = match self.color_buffer.format {
-                    4 => raylib_color.a, // PIXELFORMAT_UNCOMPRESSED_R8G8B8A8
-                    3 => 255, // PIXELFORMAT_UNCOMPRESSED_R8G8B8 (sin alpha)
-                    _ => 255, // Por defecto, completamente opaco
-                };
-                
-                Color::new(raylib_color.r, raylib_color.g, raylib_color.b, alpha)
-            }
+            unpack_color(self.color_buffer[(y * self.width + x) as usize])
         } else {
             Color::BLACK
         }
@@ -85,17 +180,280 @@ impl Framebuffer {
     // Establecer el color de un píxel específico
     pub fn set_pixel_color(&mut self, x: u32, y: u32, color: Color) {
         if x < self.width && y < self.height {
-            self.color_buffer.draw_pixel(x as i32, y as i32, color);
+            self.color_buffer[(y * self.width + x) as usize] = pack_color(color);
         }
     }
     // Dibujar un rectángulo relleno (más eficiente que píxel por píxel)
     pub fn draw_rectangle(&mut self, x: u32, y: u32, width: u32, height: u32) {
+        let end_x = (x + width).min(self.width);
+        let end_y = (y + height).min(self.height);
+        if x >= end_x {
+            return;
+        }
+        let color = pack_color(self.current_color);
+        for py in y..end_y {
+            self.row_mut(py)[x as usize..end_x as usize].fill(color);
+        }
+    }
+    // Mezclar un color sobre un píxel (ver `BlendMode`)
+    pub fn blend_pixel(&mut self, x: u32, y: u32, color: Color, mode: BlendMode) {
+        if x < self.width && y < self.height {
+            let index = (y * self.width + x) as usize;
+            let blended = blend_colors(unpack_color(self.color_buffer[index]), color, mode);
+            self.color_buffer[index] = pack_color(blended);
+        }
+    }
+    // Mezclar un color sobre un rectángulo (recortado a la pantalla)
+    pub fn blend_rectangle(&mut self, x: u32, y: u32, width: u32, height: u32, color: Color, mode: BlendMode) {
         let end_x = (x + width).min(self.width);
         let end_y = (y + height).min(self.height);
         for py in y..end_y {
             for px in x..end_x {
-                self.color_buffer.draw_pixel(px as i32, py as i32, self.current_color);
+                self.blend_pixel(px, py, color, mode);
             }
         }
     }
+    // Contorno de un rectángulo de 1 píxel de grosor con el color actual
+    pub fn draw_rectangle_outline(&mut self, x: u32, y: u32, width: u32, height: u32) {
+        if width == 0 || height == 0 {
+            return;
+        }
+        self.draw_rectangle(x, y, width, 1);
+        self.draw_rectangle(x, y + height - 1, width, 1);
+        self.draw_rectangle(x, y, 1, height);
+        self.draw_rectangle(x + width - 1, y, 1, height);
+    }
+    // Círculo relleno con el color actual; el centro puede quedar fuera de la pantalla
+    pub fn draw_circle(&mut self, center_x: i32, center_y: i32, radius: i32) {
+        for dy in -radius..=radius {
+            for dx in -radius..=radius {
+                if dx * dx + dy * dy <= radius * radius {
+                    self.set_pixel_signed(center_x + dx, center_y + dy);
+                }
+            }
+        }
+    }
+    // Contorno de un círculo (algoritmo del punto medio)
+    pub fn draw_circle_outline(&mut self, center_x: i32, center_y: i32, radius: i32) {
+        let mut x = radius;
+        let mut y = 0;
+        let mut error = 1 - radius;
+        while x >= y {
+            for (px, py) in [(x, y), (y, x), (-y, x), (-x, y), (-x, -y), (-y, -x), (y, -x), (x, -y)] {
+                self.set_pixel_signed(center_x + px, center_y + py);
+            }
+            y += 1;
+            if error < 0 {
+                error += 2 * y + 1;
+            } else {
+                x -= 1;
+                error += 2 * (y - x) + 1;
+            }
+        }
+    }
+    fn set_pixel_signed(&mut self, x: i32, y: i32) {
+        if x >= 0 && y >= 0 {
+            self.set_pixel(x as u32, y as u32);
+        }
+    }
+    // Rectángulo relleno con coordenadas con signo (recortado a la pantalla)
+    fn fill_rect_signed(&mut self, x: i32, y: i32, width: u32, height: u32, color: Color) {
+        let (start_x, end_x) = Framebuffer::clip(x, width, self.width);
+        let (start_y, end_y) = Framebuffer::clip(y, height, self.height);
+        if start_x >= end_x {
+            return;
+        }
+        let color = pack_color(color);
+        for py in start_y..end_y {
+            self.row_mut(py as u32)[start_x as usize..end_x as usize].fill(color);
+        }
+    }
+    // Escribir texto con una fuente de mapa de bits; (x, y) es la esquina superior de la primera
+    // línea según la alineación. Cada '\n' empieza una línea nueva
+    pub fn draw_text(&mut self, font: &BitmapFont, text: &str, x: i32, y: i32, style: &TextStyle) {
+        let scale = style.scale.max(1);
+        for (line_index, line) in text.split('\n').enumerate() {
+            let width = font.text_width(line, scale) as i32;
+            let line_x = match style.align {
+                TextAlign::Left => x,
+                TextAlign::Center => x - width / 2,
+                TextAlign::Right => x - width,
+            };
+            let line_y = y + (line_index as u32 * font.line_height(scale)) as i32;
+
+            // El contorno se dibuja primero, desplazado en las ocho direcciones, y la letra encima
+            if let Some(outline) = style.outline {
+                let step = scale as i32;
+                for (dx, dy) in [(-1, -1), (0, -1), (1, -1), (-1, 0), (1, 0), (-1, 1), (0, 1), (1, 1)] {
+                    self.draw_text_line(font, line, line_x + dx * step, line_y + dy * step, scale, outline);
+                }
+            }
+            self.draw_text_line(font, line, line_x, line_y, scale, style.color);
+        }
+    }
+    fn draw_text_line(&mut self, font: &BitmapFont, line: &str, x: i32, y: i32, scale: u32, color: Color) {
+        for (index, ch) in line.chars().enumerate() {
+            let Some(rows) = font.glyph(ch) else {
+                continue;
+            };
+            let glyph_x = x + (index as u32 * font.advance(scale)) as i32;
+            for (row_index, &row) in rows.iter().enumerate() {
+                for column in 0..font.glyph_width {
+                    if row & (1 << (font.glyph_width - 1 - column)) != 0 {
+                        let px = glyph_x + (column * scale) as i32;
+                        let py = y + (row_index as u32 * scale) as i32;
+                        self.fill_rect_signed(px, py, scale, scale, color);
+                    }
+                }
+            }
+        }
+    }
+    // Rango de filas o columnas de destino que quedan dentro de la pantalla
+    fn clip(start: i32, length: u32, limit: u32) -> (i32, i32) {
+        (start.max(0), (start + length as i32).min(limit as i32))
+    }
+    // Copiar otro framebuffer tal cual con su esquina superior izquierda en (dest_x, dest_y)
+    pub fn blit(&mut self, source: &Framebuffer, dest_x: i32, dest_y: i32) {
+        let (start_x, end_x) = Framebuffer::clip(dest_x, source.width, self.width);
+        let (start_y, end_y) = Framebuffer::clip(dest_y, source.height, self.height);
+        if start_x >= end_x {
+            return;
+        }
+        for y in start_y..end_y {
+            let src_start = ((y - dest_y) as u32 * source.width + (start_x - dest_x) as u32) as usize;
+            let src_row = &source.color_buffer[src_start..src_start + (end_x - start_x) as usize];
+            self.row_mut(y as u32)[start_x as usize..end_x as usize].copy_from_slice(src_row);
+        }
+    }
+    // Dibujar otro framebuffer encima mezclando cada píxel según su alpha y el modo
+    pub fn blit_blended(&mut self, source: &Framebuffer, dest_x: i32, dest_y: i32, mode: BlendMode) {
+        let (start_x, end_x) = Framebuffer::clip(dest_x, source.width, self.width);
+        let (start_y, end_y) = Framebuffer::clip(dest_y, source.height, self.height);
+        for y in start_y..end_y {
+            for x in start_x..end_x {
+                let color = source.get_pixel((x - dest_x) as u32, (y - dest_y) as u32);
+                self.blend_pixel(x as u32, y as u32, color, mode);
+            }
+        }
+    }
+    // Copiar otro framebuffer escalado al rectángulo de destino
+    pub fn blit_scaled(&mut self, source: &Framebuffer, dest_x: i32, dest_y: i32, dest_width: u32, dest_height: u32, filter: UpscaleFilter) {
+        if dest_width == 0 || dest_height == 0 || source.width == 0 || source.height == 0 {
+            return;
+        }
+        // Mismo tamaño: copiar las filas sin muestrear
+        if dest_width == source.width && dest_height == source.height {
+            self.blit(source, dest_x, dest_y);
+            return;
+        }
+        let scale_x = source.width as f32 / dest_width as f32;
+        let scale_y = source.height as f32 / dest_height as f32;
+        let (start_x, end_x) = Framebuffer::clip(dest_x, dest_width, self.width);
+        let (start_y, end_y) = Framebuffer::clip(dest_y, dest_height, self.height);
+
+        for y in start_y..end_y {
+            for x in start_x..end_x {
+                let color = source.sample_scaled((x - dest_x) as u32, (y - dest_y) as u32, scale_x, scale_y, filter);
+                self.color_buffer[(y as u32 * self.width + x as u32) as usize] = pack_color(color);
+            }
+        }
+    }
+    // Escalar otro framebuffer (la escena a resolución interna) para cubrir este completo
+    pub fn upscale_from(&mut self, source: &Framebuffer, filter: UpscaleFilter) {
+        // Mismo tamaño: copiar la imagen directamente
+        if source.width == self.width && source.height == self.height {
+            self.color_buffer.copy_from_slice(&source.color_buffer);
+            return;
+        }
+        self.blit_scaled(source, 0, 0, self.width, self.height, filter);
+    }
+    // Color de este framebuffer visto a través de un escalado: (x, y) es el píxel de destino
+    fn sample_scaled(&self, x: u32, y: u32, scale_x: f32, scale_y: f32, filter: UpscaleFilter) -> Color {
+        match filter {
+            UpscaleFilter::Nearest => {
+                let src_x = ((x as f32 * scale_x) as u32).min(self.width - 1);
+                let src_y = ((y as f32 * scale_y) as u32).min(self.height - 1);
+                self.get_pixel(src_x, src_y)
+            }
+            UpscaleFilter::Bilinear => {
+                // Muestrear en el centro del píxel de destino
+                let src_x = ((x as f32 + 0.5) * scale_x - 0.5).max(0.0);
+                let src_y = ((y as f32 + 0.5) * scale_y - 0.5).max(0.0);
+                let x0 = (src_x as u32).min(self.width - 1);
+                let y0 = (src_y as u32).min(self.height - 1);
+                let x1 = (x0 + 1).min(self.width - 1);
+                let y1 = (y0 + 1).min(self.height - 1);
+                let fx = src_x - x0 as f32;
+                let fy = src_y - y0 as f32;
+
+                let c00 = self.get_pixel(x0, y0);
+                let c10 = self.get_pixel(x1, y0);
+                let c01 = self.get_pixel(x0, y1);
+                let c11 = self.get_pixel(x1, y1);
+
+                let lerp = |a: u8, b: u8, c: u8, d: u8| -> u8 {
+                    let top = a as f32 + (b as f32 - a as f32) * fx;
+                    let bottom = c as f32 + (d as f32 - c as f32) * fx;
+                    (top + (bottom - top) * fy) as u8
+                };
+
+                Color::new(
+                    lerp(c00.r, c10.r, c01.r, c11.r),
+                    lerp(c00.g, c10.g, c01.g, c11.g),
+                    lerp(c00.b, c10.b, c01.b, c11.b),
+                    lerp(c00.a, c10.a, c01.a, c11.a),
+                )
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn alpha_blend_interpolates_by_source_alpha() {
+        let dst = Color::new(0, 100, 200, 255);
+        assert_eq!(blend_colors(dst, Color::new(200, 200, 200, 255), BlendMode::Alpha), Color::new(200, 200, 200, 255));
+        assert_eq!(blend_colors(dst, Color::new(200, 200, 200, 0), BlendMode::Alpha), dst);
+        assert_eq!(blend_colors(dst, Color::new(200, 200, 0, 51), BlendMode::Alpha), Color::new(40, 120, 160, 255));
+    }
+
+    #[test]
+    fn additive_and_multiply_blends_clamp_and_keep_destination_alpha() {
+        let dst = Color::new(200, 100, 50, 128);
+        assert_eq!(blend_colors(dst, Color::new(100, 100, 100, 255), BlendMode::Additive), Color::new(255, 200, 150, 128));
+        assert_eq!(blend_colors(dst, Color::new(255, 0, 51, 255), BlendMode::Multiply), Color::new(200, 0, 10, 128));
+        // Con medio alpha el multiplicado se aplica a medias
+        assert_eq!(blend_colors(dst, Color::new(0, 0, 0, 102), BlendMode::Multiply), Color::new(120, 60, 30, 128));
+    }
+
+    #[test]
+    fn blit_clips_to_the_destination() {
+        let mut source = Framebuffer::new(3, 2, Color::BLACK);
+        for y in 0..2 {
+            for x in 0..3 {
+                source.set_pixel_color(x, y, Color::new(x as u8, y as u8, 7, 255));
+            }
+        }
+        let mut dest = Framebuffer::new(4, 4, Color::WHITE);
+        dest.blit(&source, -1, 3);
+
+        // Solo entran las columnas 1 y 2 de la primera fila del origen
+        assert_eq!(dest.get_pixel(0, 3), Color::new(1, 0, 7, 255));
+        assert_eq!(dest.get_pixel(1, 3), Color::new(2, 0, 7, 255));
+        assert_eq!(dest.get_pixel(2, 3), Color::WHITE);
+        assert_eq!(dest.get_pixel(0, 2), Color::WHITE);
+    }
+
+    #[test]
+    fn blend_pixel_outside_the_framebuffer_is_ignored() {
+        let mut framebuffer = Framebuffer::new(2, 2, Color::BLACK);
+        framebuffer.blend_pixel(2, 0, Color::WHITE, BlendMode::Additive);
+        framebuffer.blend_rectangle(1, 1, 5, 5, Color::WHITE, BlendMode::Additive);
+        assert_eq!(framebuffer.get_pixel(1, 1), Color::WHITE);
+        assert_eq!(framebuffer.get_pixel(0, 0), Color::BLACK);
+        assert_eq!(framebuffer.get_pixel(1, 0), Color::BLACK);
+    }
 }
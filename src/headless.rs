@@ -0,0 +1,357 @@
+// headless.rs - Render de una cámara fija sin ventana, para pruebas de imágenes de referencia
+//
+// Uso: MazeRunner --headless <nivel> <x> <y> <ángulo> <salida.png> [opciones]
+// Opciones de la toma: --time <segundos> (tiempo de las animaciones, las luces y los efectos),
+// --anxiety <0..1> y --damage <0..1> (intensidad de esos efectos de pantalla).
+// Con --golden <referencia.png> se compara el resultado contra la referencia y el proceso termina
+// con código 1 si alguna diferencia supera la tolerancia. Con --bench <frames> se dibuja la escena
+// esa cantidad de veces y se imprime el tiempo promedio por frame.
+// Las cámaras de referencia están en golden/cameras.txt (ver scripts/golden.sh).
+
+use raylib::prelude::*;
+
+use crate::framebuffer::Framebuffer;
+use crate::level::Level;
+use crate::maze::{find_triggers, MazeError};
+use crate::palette::apply_retro_mode;
+use crate::player::Player;
+use crate::renderer::{render_minimap, render_scene, Viewport};
+use crate::settings::{QualityPreset, RenderSettings};
+use crate::sprites::{update_sprite_distances, Sprite};
+use crate::textures::TextureManager;
+use crate::visual_effects::{PostPipeline, VisualEffects};
+use std::time::{Duration, Instant};
+
+pub const HEADLESS_WIDTH: u32 = 1300;
+pub const HEADLESS_HEIGHT: u32 = 900;
+const BLOCK_SIZE: usize = 100;
+
+// Diferencia máxima por canal que se acepta entre un píxel y su referencia
+const GOLDEN_TOLERANCE: u8 = 2;
+
+// Cámara fija desde la que se renderiza
+pub struct Camera {
+    pub x: f32,
+    pub y: f32,
+    pub angle: f32,
+    pub pitch: f32,
+}
+
+// Estado del juego que no sale del nivel: los efectos de pantalla y el tiempo
+#[derive(Clone)]
+pub struct SceneState {
+    pub visual_effects: VisualEffects,
+    pub time: f32,
+}
+
+impl SceneState {
+    // Al empezar el juego: efectos en reposo y tiempo 0
+    pub fn initial() -> Self {
+        SceneState { visual_effects: VisualEffects::new(), time: 0.0 }
+    }
+}
+
+// Una toma: qué nivel, desde dónde y en qué momento
+pub struct Shot {
+    pub level_file: String,
+    pub camera: Camera,
+    pub state: SceneState,
+}
+
+impl Shot {
+    // Leer `<nivel> <x> <y> <ángulo> [--time s] [--anxiety n] [--damage n]`
+    pub fn parse(args: &[impl AsRef<str>]) -> Option<Shot> {
+        let [level_file, x, y, angle, options @ ..] = args else {
+            return None;
+        };
+        let camera = Camera {
+            x: x.as_ref().parse().ok()?,
+            y: y.as_ref().parse().ok()?,
+            angle: angle.as_ref().parse().ok()?,
+            pitch: 0.0,
+        };
+        let mut state = SceneState::initial();
+        for option in options.chunks(2) {
+            let [flag, value] = option else {
+                return None;
+            };
+            let value: f32 = value.as_ref().parse().ok()?;
+            match flag.as_ref() {
+                "--time" => state.time = value,
+                "--anxiety" => state.visual_effects.anxiety_level = value,
+                "--damage" => state.visual_effects.damage_effect = value,
+                _ => return None,
+            }
+        }
+        Some(Shot { level_file: level_file.as_ref().to_string(), camera, state })
+    }
+}
+
+// Todo lo necesario para dibujar un nivel desde una cámara fija
+struct HeadlessScene {
+    texture_manager: TextureManager,
+    level: Level,
+    player: Player,
+    sprites: Vec<Sprite>,
+    render_settings: RenderSettings,
+    post_pipeline: PostPipeline,
+    scene_framebuffer: Framebuffer,
+    state: SceneState,
+}
+
+impl HeadlessScene {
+    fn new(shot: &Shot, width: u32, height: u32, preset: QualityPreset) -> Result<Self, MazeError> {
+        let camera = &shot.camera;
+        let mut level = Level::load(&shot.level_file, BLOCK_SIZE)?;
+        let mut texture_manager = TextureManager::load_from_disk();
+        texture_manager.load_level_textures(&shot.level_file);
+        // Las animaciones, las luces y los efectos usan el mismo reloj, como en el juego
+        texture_manager.update(shot.state.time);
+        level.light_map.update(shot.state.time);
+
+        let mut player = Player::new(camera.x, camera.y, camera.angle, std::f32::consts::PI * 2.0 / 3.0);
+        player.look_vertical(camera.pitch);
+
+        // Como si el jugador ya hubiera pasado por todos los triggers: un sprite en cada uno,
+        // creado igual que en el juego y ordenado por distancia a la cámara
+        let mut sprites: Vec<Sprite> = find_triggers(&level.maze, BLOCK_SIZE)
+            .into_iter()
+            .map(|(x, y, trigger_char)| Sprite::new(x, y, trigger_char))
+            .collect();
+        update_sprite_distances(&mut sprites, &player);
+
+        let render_settings = RenderSettings::from_preset(preset, width, height);
+        let mut scene_framebuffer = Framebuffer::new(render_settings.render_width, render_settings.render_height, Color::BLACK);
+        scene_framebuffer.set_background_color(Color::new(50, 50, 100, 255));
+        let post_pipeline = level.post_pipeline(&render_settings);
+
+        Ok(HeadlessScene {
+            texture_manager,
+            level,
+            player,
+            sprites,
+            render_settings,
+            post_pipeline,
+            scene_framebuffer,
+            state: shot.state.clone(),
+        })
+    }
+
+    // Dibujar la vista 3D en `scene_framebuffer`
+    fn render(&mut self) {
+        render_scene(
+            &mut self.scene_framebuffer,
+            &self.player,
+            &self.level,
+            &self.sprites,
+            &self.texture_manager,
+            &self.render_settings,
+            &self.post_pipeline,
+            &self.state.visual_effects,
+            self.state.time,
+        );
+    }
+}
+
+// Renderizar una toma a un framebuffer en memoria
+// Todo es determinista: el tiempo y los efectos son los de la toma y los sprites no se mueven
+pub fn render_headless(shot: &Shot, width: u32, height: u32, preset: QualityPreset) -> Result<Framebuffer, MazeError> {
+    let mut scene = HeadlessScene::new(shot, width, height, preset)?;
+    scene.render();
+
+    let mut framebuffer = Framebuffer::new(width, height, Color::BLACK);
+    framebuffer.upscale_from(&scene.scene_framebuffer, scene.render_settings.upscale_filter);
+    render_minimap(&mut framebuffer, &Viewport::full(width, height), &scene.level.maze, &scene.player, &scene.sprites, BLOCK_SIZE);
+    if let Some(mode) = &scene.level.retro_mode {
+        apply_retro_mode(&mut framebuffer, mode);
+    }
+    Ok(framebuffer)
+}
+
+// Tiempo promedio en dibujar la vista 3D (sin cargar el nivel ni escalar), para comparar
+// el rendimiento del render entre versiones
+pub fn benchmark(shot: &Shot, width: u32, height: u32, preset: QualityPreset, frames: u32) -> Result<Duration, MazeError> {
+    let mut scene = HeadlessScene::new(shot, width, height, preset)?;
+    scene.render(); // el primer frame calienta las cachés
+
+    let start = Instant::now();
+    for _ in 0..frames {
+        scene.render();
+    }
+    Ok(start.elapsed() / frames.max(1))
+}
+
+// Resultado de comparar un render contra su imagen de referencia
+pub struct GoldenDiff {
+    pub differing_pixels: usize,
+    pub max_difference: u8,
+}
+
+impl GoldenDiff {
+    pub fn matches(&self) -> bool {
+        self.differing_pixels == 0
+    }
+}
+
+// Contar los píxeles cuya diferencia con la referencia supera la tolerancia
+// Devuelve None si los tamaños no coinciden
+pub fn compare_to_golden(rendered: &Framebuffer, golden: &Framebuffer) -> Option<GoldenDiff> {
+    if rendered.width != golden.width || rendered.height != golden.height {
+        return None;
+    }
+
+    let mut diff = GoldenDiff { differing_pixels: 0, max_difference: 0 };
+    for y in 0..rendered.height {
+        for x in 0..rendered.width {
+            let a = rendered.get_pixel(x, y);
+            let b = golden.get_pixel(x, y);
+            let difference = a.r.abs_diff(b.r).max(a.g.abs_diff(b.g)).max(a.b.abs_diff(b.b));
+            diff.max_difference = diff.max_difference.max(difference);
+            if difference > GOLDEN_TOLERANCE {
+                diff.differing_pixels += 1;
+            }
+        }
+    }
+    Some(diff)
+}
+
+// Atender la línea de comandos `--headless`; devuelve el código de salida del proceso
+pub fn run(args: &[String]) -> i32 {
+    let usage = "Uso: --headless <nivel> <x> <y> <ángulo> <salida.png> [--time <segundos>] [--anxiety <0..1>] [--damage <0..1>] [--golden <referencia.png>] [--bench <frames>]";
+    let [level_file, x, y, angle, output, rest @ ..] = args else {
+        eprintln!("{}", usage);
+        return 2;
+    };
+    let mut shot_args = vec![level_file, x, y, angle];
+    let mut golden = None;
+    let mut bench_frames = None;
+    for option in rest.chunks(2) {
+        match option {
+            [flag, path] if flag == "--golden" => golden = Some(path),
+            [flag, frames] if flag == "--bench" && frames.parse::<u32>().is_ok() => bench_frames = frames.parse().ok(),
+            [flag, value] => shot_args.extend([flag, value]),
+            _ => {
+                eprintln!("{}", usage);
+                return 2;
+            }
+        }
+    }
+    let Some(shot) = Shot::parse(&shot_args) else {
+        eprintln!("{}", usage);
+        return 2;
+    };
+
+    if let Some(frames) = bench_frames {
+        let frame_time = match benchmark(&shot, HEADLESS_WIDTH, HEADLESS_HEIGHT, QualityPreset::High, frames) {
+            Ok(frame_time) => frame_time,
+            Err(error) => {
+                eprintln!("No se pudo cargar el nivel {}: {}", level_file, error);
+                return 2;
+            }
+        };
+        println!("{} frames, {:.2} ms por frame", frames, frame_time.as_secs_f64() * 1000.0);
+    }
+    let framebuffer = match render_headless(&shot, HEADLESS_WIDTH, HEADLESS_HEIGHT, QualityPreset::High) {
+        Ok(framebuffer) => framebuffer,
+        Err(error) => {
+            eprintln!("No se pudo cargar el nivel {}: {}", level_file, error);
+            return 2;
+        }
+    };
+    framebuffer.render_to_file(output);
+
+    let Some(golden_path) = golden else {
+        return 0;
+    };
+    let Some(golden) = Framebuffer::load_from_file(golden_path) else {
+        eprintln!("No se pudo cargar la imagen de referencia {}", golden_path);
+        return 2;
+    };
+    match compare_to_golden(&framebuffer, &golden) {
+        Some(diff) if diff.matches() => 0,
+        Some(diff) => {
+            eprintln!(
+                "{} píxeles difieren de {} (diferencia máxima {})",
+                diff.differing_pixels, golden_path, diff.max_difference
+            );
+            1
+        }
+        None => {
+            eprintln!("El tamaño de {} no coincide con el render", golden_path);
+            1
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn framebuffer_with_pixel(width: u32, height: u32, x: u32, y: u32, color: Color) -> Framebuffer {
+        let mut framebuffer = Framebuffer::new(width, height, Color::BLACK);
+        framebuffer.set_pixel_color(x, y, color);
+        framebuffer
+    }
+
+    #[test]
+    fn differences_within_tolerance_still_match() {
+        let golden = framebuffer_with_pixel(4, 3, 1, 1, Color::new(100, 100, 100, 255));
+        let rendered = framebuffer_with_pixel(4, 3, 1, 1, Color::new(102, 99, 100, 255));
+        let diff = compare_to_golden(&rendered, &golden).unwrap();
+        assert!(diff.matches());
+        assert_eq!(diff.max_difference, 2);
+    }
+
+    #[test]
+    fn differences_over_tolerance_are_counted() {
+        let golden = framebuffer_with_pixel(4, 3, 1, 1, Color::new(100, 100, 100, 255));
+        let rendered = framebuffer_with_pixel(4, 3, 1, 1, Color::new(100, 100, 103, 255));
+        let diff = compare_to_golden(&rendered, &golden).unwrap();
+        assert!(!diff.matches());
+        assert_eq!(diff.differing_pixels, 1);
+        assert_eq!(diff.max_difference, 3);
+    }
+
+    #[test]
+    fn different_sizes_do_not_compare() {
+        let golden = Framebuffer::new(4, 3, Color::BLACK);
+        assert!(compare_to_golden(&Framebuffer::new(3, 4, Color::BLACK), &golden).is_none());
+        assert!(compare_to_golden(&Framebuffer::new(4, 4, Color::BLACK), &golden).is_none());
+    }
+
+    #[test]
+    fn shot_reads_camera_and_effect_options() {
+        let shot = Shot::parse(&["maze.txt", "150", "250", "0.5", "--time", "2", "--damage", "0.5"]).unwrap();
+        assert_eq!(shot.level_file, "maze.txt");
+        assert_eq!((shot.camera.x, shot.camera.y, shot.camera.angle), (150.0, 250.0, 0.5));
+        assert_eq!(shot.state.time, 2.0);
+        assert_eq!(shot.state.visual_effects.damage_effect, 0.5);
+        assert_eq!(shot.state.visual_effects.anxiety_level, 0.0);
+
+        assert!(Shot::parse(&["maze.txt", "150", "250"]).is_none());
+        assert!(Shot::parse(&["maze.txt", "150", "250", "0.5", "--time"]).is_none());
+        assert!(Shot::parse(&["maze.txt", "150", "250", "0.5", "--speed", "2"]).is_none());
+    }
+
+    // La misma comprobación que scripts/golden.sh, para que corra con `cargo test`
+    #[test]
+    fn golden_cameras_match_their_references() {
+        let cameras = std::fs::read_to_string("golden/cameras.txt").expect("Falta golden/cameras.txt");
+        for line in cameras.lines().filter(|line| !line.trim().is_empty() && !line.starts_with('#')) {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            let shot = Shot::parse(&words[1..]).unwrap_or_else(|| panic!("Cámara mal escrita: {}", line));
+            let rendered = render_headless(&shot, HEADLESS_WIDTH, HEADLESS_HEIGHT, QualityPreset::High).expect("No se pudo cargar el nivel");
+
+            let golden_path = format!("golden/{}.png", words[0]);
+            let golden = Framebuffer::load_from_file(&golden_path).unwrap_or_else(|| panic!("Falta la referencia {}", golden_path));
+            let diff = compare_to_golden(&rendered, &golden).unwrap_or_else(|| panic!("El tamaño de {} no coincide con el render", golden_path));
+            assert!(
+                diff.matches(),
+                "{} píxeles difieren de {} (diferencia máxima {}); regenerar con scripts/golden.sh --update si el cambio es intencional",
+                diff.differing_pixels,
+                golden_path,
+                diff.max_difference
+            );
+        }
+    }
+}
@@ -0,0 +1,61 @@
+// hot_reload.rs - Recarga en caliente para el modo de desarrollo (--dev)
+//
+// Vigila la fecha de modificación de los archivos del nivel actual (el laberinto, el manifiesto
+// de texturas, las imágenes y la paleta) y avisa cuáles cambiaron. Se revisa cada cierto tiempo
+// en vez de cada frame, porque pedir la fecha de muchos archivos no es gratis.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+pub struct FileWatcher {
+    files: HashMap<PathBuf, Option<SystemTime>>, // None si el archivo no existe
+    interval: Duration,
+    last_poll: Instant,
+}
+
+impl FileWatcher {
+    pub fn new(interval: Duration) -> Self {
+        FileWatcher {
+            files: HashMap::new(),
+            interval,
+            last_poll: Instant::now(),
+        }
+    }
+
+    // Empezar a vigilar un archivo (puede no existir todavía)
+    pub fn watch(&mut self, path: impl AsRef<Path>) {
+        let path = path.as_ref().to_path_buf();
+        let modified = modified_time(&path);
+        self.files.insert(path, modified);
+    }
+
+    // Dejar de vigilar todo (al cambiar de nivel)
+    pub fn clear(&mut self) {
+        self.files.clear();
+    }
+
+    // Archivos que cambiaron, se crearon o se borraron desde la revisión anterior
+    // Devuelve una lista vacía si todavía no pasó el intervalo
+    pub fn poll(&mut self) -> Vec<PathBuf> {
+        if self.last_poll.elapsed() < self.interval {
+            return Vec::new();
+        }
+        self.last_poll = Instant::now();
+
+        let mut changed = Vec::new();
+        for (path, last_modified) in self.files.iter_mut() {
+            let modified = modified_time(path);
+            if modified != *last_modified {
+                *last_modified = modified;
+                changed.push(path.clone());
+            }
+        }
+        changed
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
@@ -0,0 +1,121 @@
+// input.rs - Controles de cada jugador
+//
+// El movimiento no lee el teclado directamente: cada frame se traduce el dispositivo del
+// jugador (teclado y ratón, teclas alternativas o un gamepad) a un PlayerInput.
+
+use raylib::prelude::*;
+use std::f32::consts::PI;
+
+const ROTATION_SPEED: f32 = PI / 10.0;
+const MOUSE_SENSITIVITY: f32 = 0.003; // Sensibilidad del mouse para rotación
+const PITCH_MOUSE_SENSITIVITY: f32 = 0.002; // Sensibilidad del mouse para mirar arriba/abajo
+const PITCH_KEY_SPEED: f32 = 0.03;
+const STICK_DEAD_ZONE: f32 = 0.2; // Los sticks nunca vuelven exactamente a 0
+
+// Dispositivo con el que se controla un jugador
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Controls {
+    // Flechas, RePág/AvPág, Ctrl o C, espacio, E y el ratón
+    KeyboardMouse,
+    // I/K avanzar y retroceder, J/L girar, U/O mirar arriba y abajo, N agacharse, H saltar, Y puertas
+    AltKeys,
+    // Stick izquierdo para moverse, derecho para girar y mirar; A salta, B agacha, X puertas
+    Gamepad(i32),
+}
+
+impl Controls {
+    // El segundo jugador usa el primer gamepad si hay uno conectado, si no las teclas alternativas
+    pub fn second_player(window: &RaylibHandle) -> Controls {
+        if window.is_gamepad_available(0) {
+            Controls::Gamepad(0)
+        } else {
+            Controls::AltKeys
+        }
+    }
+}
+
+// Lo que un jugador quiere hacer en este frame, sin importar el dispositivo
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PlayerInput {
+    pub forward: f32, // -1.0 (atrás) a 1.0 (adelante)
+    pub turn: f32,    // radianes a girar
+    pub look: f32,    // cambio de la inclinación de la vista
+    pub crouch: bool, // se mantiene
+    pub jump: bool,   // solo el frame en que se pulsa
+    pub use_door: bool,
+}
+
+impl PlayerInput {
+    // `mouse_rotation` solo se usa con Controls::KeyboardMouse
+    pub fn read(window: &RaylibHandle, controls: Controls, mouse_rotation: bool) -> Self {
+        match controls {
+            Controls::KeyboardMouse => {
+                let keys = PlayerInput::from_keys(
+                    window,
+                    [
+                        KeyboardKey::KEY_UP,
+                        KeyboardKey::KEY_DOWN,
+                        KeyboardKey::KEY_LEFT,
+                        KeyboardKey::KEY_RIGHT,
+                        KeyboardKey::KEY_PAGE_UP,
+                        KeyboardKey::KEY_PAGE_DOWN,
+                    ],
+                    window.is_key_down(KeyboardKey::KEY_LEFT_CONTROL) || window.is_key_down(KeyboardKey::KEY_C),
+                    KeyboardKey::KEY_SPACE,
+                    KeyboardKey::KEY_E,
+                );
+                if !mouse_rotation {
+                    return keys;
+                }
+                let mouse_delta = window.get_mouse_delta();
+                PlayerInput {
+                    turn: keys.turn + mouse_delta.x * MOUSE_SENSITIVITY,
+                    look: keys.look - mouse_delta.y * PITCH_MOUSE_SENSITIVITY,
+                    ..keys
+                }
+            }
+            Controls::AltKeys => PlayerInput::from_keys(
+                window,
+                [
+                    KeyboardKey::KEY_I,
+                    KeyboardKey::KEY_K,
+                    KeyboardKey::KEY_J,
+                    KeyboardKey::KEY_L,
+                    KeyboardKey::KEY_U,
+                    KeyboardKey::KEY_O,
+                ],
+                window.is_key_down(KeyboardKey::KEY_N),
+                KeyboardKey::KEY_H,
+                KeyboardKey::KEY_Y,
+            ),
+            Controls::Gamepad(gamepad) => {
+                let axis = |axis: GamepadAxis| {
+                    let value = window.get_gamepad_axis_movement(gamepad, axis);
+                    if value.abs() < STICK_DEAD_ZONE { 0.0 } else { value }
+                };
+                PlayerInput {
+                    forward: -axis(GamepadAxis::GAMEPAD_AXIS_LEFT_Y),
+                    turn: axis(GamepadAxis::GAMEPAD_AXIS_RIGHT_X) * ROTATION_SPEED,
+                    look: -axis(GamepadAxis::GAMEPAD_AXIS_RIGHT_Y) * PITCH_KEY_SPEED,
+                    crouch: window.is_gamepad_button_down(gamepad, GamepadButton::GAMEPAD_BUTTON_RIGHT_FACE_RIGHT),
+                    jump: window.is_gamepad_button_pressed(gamepad, GamepadButton::GAMEPAD_BUTTON_RIGHT_FACE_DOWN),
+                    use_door: window.is_gamepad_button_pressed(gamepad, GamepadButton::GAMEPAD_BUTTON_RIGHT_FACE_LEFT),
+                }
+            }
+        }
+    }
+
+    // Teclas de dirección en orden: adelante, atrás, izquierda, derecha, mirar arriba, mirar abajo
+    fn from_keys(window: &RaylibHandle, directions: [KeyboardKey; 6], crouch: bool, jump: KeyboardKey, use_door: KeyboardKey) -> Self {
+        let [forward, back, left, right, up, down] = directions.map(|key| window.is_key_down(key));
+        let axis = |positive: bool, negative: bool| positive as i32 as f32 - negative as i32 as f32;
+        PlayerInput {
+            forward: axis(forward, back),
+            turn: axis(right, left) * ROTATION_SPEED,
+            look: axis(up, down) * PITCH_KEY_SPEED,
+            crouch,
+            jump: window.is_key_pressed(jump),
+            use_door: window.is_key_pressed(use_door),
+        }
+    }
+}
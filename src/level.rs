@@ -0,0 +1,94 @@
+// level.rs - Estado de un nivel cargado desde su archivo
+//
+// Además de los decals, el archivo puede activar o desactivar efectos de post-procesado
+// y elegir una paleta retro (ver palette.rs):
+//   #effect <nombre> on|off   (fog, flashlight, anxiety, damage)
+//   #palette <paleta> [tramado]
+// Las líneas '#texture' las lee el TextureManager (ver textures.rs y assets/textures.txt).
+
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+
+use crate::decals::Decals;
+use crate::lights::{extract_lights, LightMap};
+use crate::maze::{load_maze, Doors, Maze, MazeError, Portals};
+use crate::palette::RetroMode;
+use crate::settings::RenderSettings;
+use crate::visual_effects::PostPipeline;
+
+// Todo lo que se construye a partir del archivo del laberinto
+pub struct Level {
+    pub maze: Maze,
+    pub doors: Doors,
+    pub portals: Portals,
+    pub decals: Decals,
+    pub light_map: LightMap,
+    pub effects: Vec<(String, bool)>, // efectos que el nivel activa o desactiva
+    pub retro_mode: Option<RetroMode>, // paleta de salida del nivel (línea '#palette')
+}
+
+impl Level {
+    // Falla si el laberinto no se puede leer o no es rectangular; el resto del archivo
+    // (decals, efectos, paleta) es opcional y sus líneas inválidas se ignoran
+    pub fn load(filename: &str, block_size: usize) -> Result<Self, MazeError> {
+        let mut maze = load_maze(filename)?;
+        let lights = extract_lights(&mut maze, block_size);
+        let light_map = LightMap::new(&maze, lights, block_size);
+        let doors = Doors::new(&maze);
+        let portals = Portals::new(&maze);
+        let decals = Decals::load(filename);
+        let effects = load_effects(filename);
+        let retro_mode = RetroMode::from_level_file(filename);
+
+        Ok(Level {
+            maze,
+            doors,
+            portals,
+            decals,
+            light_map,
+            effects,
+            retro_mode,
+        })
+    }
+
+    // Efectos de post-procesado de este nivel: los estándar, con los cambios del archivo del nivel;
+    // el preset de calidad puede además apagar los que son caros
+    pub fn post_pipeline(&self, render_settings: &RenderSettings) -> PostPipeline {
+        let mut pipeline = PostPipeline::standard();
+        for (name, enabled) in &self.effects {
+            pipeline.set_enabled(name, *enabled);
+        }
+
+        let preset_passes = [
+            ("flashlight", render_settings.flashlight_pass),
+            ("anxiety", render_settings.anxiety_pass),
+            ("damage", render_settings.damage_pass),
+        ];
+        for (name, allowed) in preset_passes {
+            if !allowed {
+                pipeline.set_enabled(name, false);
+            }
+        }
+        pipeline
+    }
+}
+
+// Leer las líneas '#effect' del archivo del nivel (las mal formadas se ignoran)
+fn load_effects(filename: &str) -> Vec<(String, bool)> {
+    let Ok(file) = File::open(filename) else {
+        return Vec::new();
+    };
+
+    BufReader::new(file)
+        .lines()
+        .map_while(Result::ok)
+        .filter_map(|line| {
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            match parts.as_slice() {
+                ["#effect", name, "on"] => Some((name.to_string(), true)),
+                ["#effect", name, "off"] => Some((name.to_string(), false)),
+                _ => None,
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,278 @@
+// lights.rs - Luces puntuales y mapa de luz por celda
+
+use raylib::prelude::*;
+use crate::maze::{is_open_cell, Maze, DOOR_CHAR};
+
+// Caracteres de luces en el archivo del laberinto (se reemplazan por espacios al cargar)
+pub const CANDLE_CHAR: char = 'l';
+pub const BULB_CHAR: char = 'b';
+
+// Tras un apagón, las luces tardan este tiempo en volver a encenderse parpadeando
+const BLACKOUT_RECOVERY: f32 = 1.5;
+// Cuánto aclara la luz por sí misma, aunque el color de debajo sea negro
+const LIGHT_ADD: f32 = 40.0;
+
+pub struct Light {
+    pub x: f32,
+    pub y: f32,
+    pub color: Color,
+    pub radius: f32,
+    pub intensity: f32,
+    pub flicker: f32, // 0.0 luz estable, 1.0 parpadeo máximo
+    pub off_until: f32, // tiempo de juego hasta el que la luz está apagada (apagón de un susto)
+}
+
+impl Light {
+    pub fn new(x: f32, y: f32, color: Color, radius: f32, intensity: f32, flicker: f32) -> Self {
+        Light {
+            x,
+            y,
+            color,
+            radius,
+            intensity,
+            flicker,
+            off_until: f32::NEG_INFINITY,
+        }
+    }
+
+    // Intensidad actual teniendo en cuenta el parpadeo; `seed` desfasa luces distintas
+    fn current_intensity(&self, time: f32, seed: f32) -> f32 {
+        if time < self.off_until {
+            return 0.0;
+        }
+        let noise = 0.5 + 0.5 * ((time * 13.0 + seed).sin() * (time * 7.3 + seed * 2.1).sin());
+
+        // Al volver de un apagón la luz titila a tirones, cada vez más tiempo encendida
+        let recovery = (time - self.off_until) / BLACKOUT_RECOVERY;
+        if recovery < 1.0 && noise > recovery {
+            return 0.0;
+        }
+        self.intensity * (1.0 - self.flicker * noise)
+    }
+}
+
+// Celdas que bloquean la luz (las puertas dejan pasar la luz por las rendijas)
+fn blocks_light(cell: char) -> bool {
+    !is_open_cell(cell) && cell != DOOR_CHAR
+}
+
+// Quita las luces del laberinto y las devuelve
+// Las velas ('l') y bombillas ('b') se vuelven espacios vacíos; la meta ('g') brilla sin dejar de ser pared
+pub fn extract_lights(maze: &mut Maze, block_size: usize) -> Vec<Light> {
+    let block_size_f = block_size as f32;
+    let mut lights = Vec::new();
+
+    for (row_index, row) in maze.iter_mut().enumerate() {
+        for (col_index, cell) in row.iter_mut().enumerate() {
+            let x = (col_index as f32 * block_size_f) + (block_size_f / 2.0);
+            let y = (row_index as f32 * block_size_f) + (block_size_f / 2.0);
+
+            match *cell {
+                CANDLE_CHAR => {
+                    lights.push(Light::new(x, y, Color::new(255, 170, 80, 255), 250.0, 0.9, 0.25));
+                    *cell = ' ';
+                }
+                BULB_CHAR => {
+                    lights.push(Light::new(x, y, Color::new(200, 220, 255, 255), 400.0, 1.0, 0.6));
+                    *cell = ' ';
+                }
+                'g' => {
+                    lights.push(Light::new(x, y, Color::new(80, 255, 120, 255), 300.0, 0.8, 0.0));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    lights
+}
+
+// Mapa de luz por celda
+// La visibilidad y la atenuación de cada luz se hornean una sola vez al cargar el nivel;
+// cada frame solo se combinan con el estado (encendida/parpadeo) actual de las luces
+pub struct LightMap {
+    pub lights: Vec<Light>,
+    width: usize,
+    height: usize,
+    block_size: usize,
+    open: Vec<bool>,         // celdas que reciben luz (no son paredes)
+    weights: Vec<Vec<f32>>,  // por luz, por celda
+    cells: Vec<[f32; 3]>,    // luz RGB acumulada por celda (0.0 = sin luz extra)
+}
+
+impl LightMap {
+    pub fn new(maze: &Maze, lights: Vec<Light>, block_size: usize) -> Self {
+        let height = maze.len();
+        let width = maze.iter().map(|row| row.len()).max().unwrap_or(0);
+        let block_size_f = block_size as f32;
+
+        let cell_at = |col: usize, row: usize| -> char {
+            maze.get(row).and_then(|r| r.get(col)).copied().unwrap_or('+')
+        };
+
+        let mut open = vec![false; width * height];
+        for row in 0..height {
+            for col in 0..width {
+                open[row * width + col] = !blocks_light(cell_at(col, row));
+            }
+        }
+
+        let weights = lights
+            .iter()
+            .map(|light| {
+                let light_col = (light.x / block_size_f) as usize;
+                let light_row = (light.y / block_size_f) as usize;
+                let mut light_weights = vec![0.0; width * height];
+
+                for row in 0..height {
+                    for col in 0..width {
+                        if !open[row * width + col] {
+                            continue;
+                        }
+
+                        let center_x = (col as f32 * block_size_f) + (block_size_f / 2.0);
+                        let center_y = (row as f32 * block_size_f) + (block_size_f / 2.0);
+                        let dx = center_x - light.x;
+                        let dy = center_y - light.y;
+                        let distance = (dx * dx + dy * dy).sqrt();
+                        if distance >= light.radius {
+                            continue;
+                        }
+
+                        // Recorrer la línea desde la luz hasta la celda; la celda de la luz no cuenta
+                        // (la meta brilla desde dentro de su pared)
+                        let step_size = 10.0;
+                        let num_steps = (distance / step_size) as usize;
+                        let visible = (0..num_steps).all(|step| {
+                            let t = step as f32 * step_size / distance;
+                            let x = light.x + dx * t;
+                            let y = light.y + dy * t;
+                            let c = (x / block_size_f) as usize;
+                            let r = (y / block_size_f) as usize;
+                            (c == light_col && r == light_row) || !blocks_light(cell_at(c, r))
+                        });
+
+                        if visible {
+                            let falloff = 1.0 - distance / light.radius;
+                            light_weights[row * width + col] = falloff * falloff;
+                        }
+                    }
+                }
+
+                light_weights
+            })
+            .collect();
+
+        let mut light_map = LightMap {
+            lights,
+            width,
+            height,
+            block_size,
+            open,
+            weights,
+            cells: vec![[0.0; 3]; width * height],
+        };
+        light_map.update(0.0);
+        light_map
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lights.is_empty()
+    }
+
+    // Recombinar las luces con su estado actual (llamar una vez por frame)
+    pub fn update(&mut self, time: f32) {
+        for cell in self.cells.iter_mut() {
+            *cell = [0.0; 3];
+        }
+
+        for (index, (light, light_weights)) in self.lights.iter().zip(&self.weights).enumerate() {
+            let intensity = light.current_intensity(time, index as f32 * 1.7);
+            if intensity <= 0.0 {
+                continue;
+            }
+
+            let r = light.color.r as f32 / 255.0 * intensity;
+            let g = light.color.g as f32 / 255.0 * intensity;
+            let b = light.color.b as f32 / 255.0 * intensity;
+
+            for (cell, &weight) in self.cells.iter_mut().zip(light_weights) {
+                if weight > 0.0 {
+                    cell[0] += r * weight;
+                    cell[1] += g * weight;
+                    cell[2] += b * weight;
+                }
+            }
+        }
+    }
+
+    // Apagar las luces dentro de un radio durante `duration` segundos a partir de `time`
+    // Después vuelven solas, parpadeando durante BLACKOUT_RECOVERY segundos
+    pub fn blackout_near(&mut self, x: f32, y: f32, radius: f32, time: f32, duration: f32) {
+        for light in self.lights.iter_mut() {
+            let dx = light.x - x;
+            let dy = light.y - y;
+            if (dx * dx + dy * dy).sqrt() <= radius {
+                light.off_until = light.off_until.max(time + duration);
+            }
+        }
+    }
+
+    // Luz RGB en un punto del mundo, interpolada entre los centros de las celdas vecinas
+    // Las paredes no participan en la interpolación para no oscurecer los bordes
+    pub fn sample(&self, x: f32, y: f32) -> [f32; 3] {
+        if self.lights.is_empty() || self.width == 0 || self.height == 0 {
+            return [0.0; 3];
+        }
+
+        let block_size_f = self.block_size as f32;
+        let fx = (x / block_size_f - 0.5).max(0.0);
+        let fy = (y / block_size_f - 0.5).max(0.0);
+        let col0 = (fx as usize).min(self.width - 1);
+        let row0 = (fy as usize).min(self.height - 1);
+        let col1 = (col0 + 1).min(self.width - 1);
+        let row1 = (row0 + 1).min(self.height - 1);
+        let tx = (fx - col0 as f32).min(1.0);
+        let ty = (fy - row0 as f32).min(1.0);
+
+        let samples = [
+            (col0, row0, (1.0 - tx) * (1.0 - ty)),
+            (col1, row0, tx * (1.0 - ty)),
+            (col0, row1, (1.0 - tx) * ty),
+            (col1, row1, tx * ty),
+        ];
+
+        let mut light = [0.0; 3];
+        let mut total_weight = 0.0;
+        for (col, row, weight) in samples {
+            let index = row * self.width + col;
+            if !self.open[index] || weight <= 0.0 {
+                continue;
+            }
+            for (channel, cell_channel) in light.iter_mut().zip(self.cells[index]) {
+                *channel += cell_channel * weight;
+            }
+            total_weight += weight;
+        }
+
+        if total_weight > 0.0 {
+            for channel in light.iter_mut() {
+                *channel /= total_weight;
+            }
+        }
+        light
+    }
+}
+
+// Aplicar la luz de las luces puntuales a un color ya sombreado
+// La luz tiñe el color (multiplica) y además suma su propio brillo, para que ilumine
+// también las zonas que el sombreado por distancia dejó casi negras
+pub fn apply_light(color: Color, light: [f32; 3]) -> Color {
+    let channel = |value: u8, light: f32| (value as f32 * (1.0 + light) + light * LIGHT_ADD).min(255.0) as u8;
+    Color::new(
+        channel(color.r, light[0]),
+        channel(color.g, light[1]),
+        channel(color.b, light[2]),
+        color.a,
+    )
+}
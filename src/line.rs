@@ -1,31 +1,269 @@
-// Line.rs
+// Line.rs - Primitivas 2D sobre el framebuffer
+//
+// Todas dibujan con el color actual del framebuffer (si tiene alpha se mezcla con el fondo)
+// y se recortan a la pantalla, así que los puntos pueden quedar fuera de ella.
 use raylib::prelude::*;
-use crate::framebuffer::Framebuffer;
+use crate::framebuffer::{BlendMode, Framebuffer};
 
+// Pintar un píxel con coordenadas con signo
+fn plot(framebuffer: &mut Framebuffer, x: i32, y: i32) {
+    if x < 0 || y < 0 {
+        return;
+    }
+    let color = framebuffer.current_color;
+    if color.a == 255 {
+        framebuffer.set_pixel(x as u32, y as u32);
+    } else {
+        framebuffer.blend_pixel(x as u32, y as u32, color, BlendMode::Alpha);
+    }
+}
+
+// Pintar un píxel con una cobertura parcial (0.0 a 1.0), para los bordes suavizados
+fn plot_coverage(framebuffer: &mut Framebuffer, x: i32, y: i32, coverage: f32) {
+    if x < 0 || y < 0 || coverage <= 0.0 {
+        return;
+    }
+    let color = framebuffer.current_color;
+    let alpha = (color.a as f32 * coverage.min(1.0)) as u8;
+    framebuffer.blend_pixel(x as u32, y as u32, Color::new(color.r, color.g, color.b, alpha), BlendMode::Alpha);
+}
+
+// Recortar un segmento al rectángulo de la pantalla (Cohen-Sutherland)
+// Devuelve None si el segmento queda completamente fuera
+fn clip_segment(framebuffer: &Framebuffer, start: Vector2, end: Vector2) -> Option<(Vector2, Vector2)> {
+    const LEFT: u8 = 1;
+    const RIGHT: u8 = 2;
+    const TOP: u8 = 4;
+    const BOTTOM: u8 = 8;
+
+    let max_x = framebuffer.width as f32 - 1.0;
+    let max_y = framebuffer.height as f32 - 1.0;
+    let outcode = |p: Vector2| -> u8 {
+        let mut code = 0;
+        if p.x < 0.0 {
+            code |= LEFT;
+        } else if p.x > max_x {
+            code |= RIGHT;
+        }
+        if p.y < 0.0 {
+            code |= TOP;
+        } else if p.y > max_y {
+            code |= BOTTOM;
+        }
+        code
+    };
+
+    let (mut a, mut b) = (start, end);
+    let (mut code_a, mut code_b) = (outcode(a), outcode(b));
+    loop {
+        if code_a | code_b == 0 {
+            return Some((a, b));
+        }
+        if code_a & code_b != 0 {
+            return None;
+        }
+
+        // Mover hasta el borde el extremo que está fuera
+        let code = if code_a != 0 { code_a } else { code_b };
+        let point = if code & TOP != 0 {
+            Vector2::new(a.x + (b.x - a.x) * (0.0 - a.y) / (b.y - a.y), 0.0)
+        } else if code & BOTTOM != 0 {
+            Vector2::new(a.x + (b.x - a.x) * (max_y - a.y) / (b.y - a.y), max_y)
+        } else if code & RIGHT != 0 {
+            Vector2::new(max_x, a.y + (b.y - a.y) * (max_x - a.x) / (b.x - a.x))
+        } else {
+            Vector2::new(0.0, a.y + (b.y - a.y) * (0.0 - a.x) / (b.x - a.x))
+        };
+
+        if code == code_a {
+            a = point;
+            code_a = outcode(a);
+        } else {
+            b = point;
+            code_b = outcode(b);
+        }
+    }
+}
+
+// Línea de Bresenham entre dos puntos (se redondean al píxel más cercano)
 pub fn line(
     framebuffer: &mut Framebuffer,
     start: Vector2,
     end: Vector2,
 ){
-    let dx = (end.x - start.x).abs();
-    let dy = (end.y - start.y).abs();
-    let sx = if start.x < end.x { 1.0 } else { -1.0 };
-    let sy = if start.y < end.y { 1.0 } else { -1.0 };
-    let mut err = dx - dy;
+    if let Some((start, end)) = clip_segment(framebuffer, start, end) {
+        line_i32(framebuffer, start.x.round() as i32, start.y.round() as i32, end.x.round() as i32, end.y.round() as i32);
+    }
+}
 
-    let mut x = start.x;
-    let mut y = start.y;
+// Línea de Bresenham con extremos enteros (incluye ambos extremos)
+pub fn line_i32(framebuffer: &mut Framebuffer, x0: i32, y0: i32, x1: i32, y1: i32) {
+    let dx = (x1 - x0).abs();
+    let dy = -(y1 - y0).abs();
+    let sx = if x0 < x1 { 1 } else { -1 };
+    let sy = if y0 < y1 { 1 } else { -1 };
+    let mut err = dx + dy;
 
-    while x != end.x || y != end.y {
-        framebuffer.set_pixel(x as u32, y as u32);
-        let err2 = err * 2.0;
-        if err2 > -dy {
-            err -= dy;
+    let (mut x, mut y) = (x0, y0);
+    loop {
+        plot(framebuffer, x, y);
+        if x == x1 && y == y1 {
+            break;
+        }
+        let err2 = err * 2;
+        if err2 >= dy {
+            err += dy;
             x += sx;
         }
-        if err2 < dx {
+        if err2 <= dx {
             err += dx;
             y += sy;
         }
     }
-}
\ No newline at end of file
+}
+
+// Línea suavizada (algoritmo de Xiaolin Wu): cada píxel se mezcla según cuánto lo cubre la línea
+pub fn line_aa(framebuffer: &mut Framebuffer, start: Vector2, end: Vector2) {
+    let Some((start, end)) = clip_segment(framebuffer, start, end) else {
+        return;
+    };
+
+    // Recorrer siempre el eje más largo, de menor a mayor
+    let steep = (end.y - start.y).abs() > (end.x - start.x).abs();
+    let (mut a, mut b) = if steep {
+        (Vector2::new(start.y, start.x), Vector2::new(end.y, end.x))
+    } else {
+        (start, end)
+    };
+    if a.x > b.x {
+        std::mem::swap(&mut a, &mut b);
+    }
+
+    let dx = b.x - a.x;
+    let gradient = if dx == 0.0 { 1.0 } else { (b.y - a.y) / dx };
+    let plot_steep = |framebuffer: &mut Framebuffer, major: i32, minor: i32, coverage: f32| {
+        if steep {
+            plot_coverage(framebuffer, minor, major, coverage);
+        } else {
+            plot_coverage(framebuffer, major, minor, coverage);
+        }
+    };
+
+    let start_x = a.x.round() as i32;
+    let end_x = b.x.round() as i32;
+    let mut y = a.y + gradient * (start_x as f32 - a.x);
+    for x in start_x..=end_x {
+        let base = y.floor();
+        let fraction = y - base;
+        plot_steep(framebuffer, x, base as i32, 1.0 - fraction);
+        plot_steep(framebuffer, x, base as i32 + 1, fraction);
+        y += gradient;
+    }
+}
+
+// Línea de `thickness` píxeles de grosor (un rectángulo girado)
+pub fn thick_line(framebuffer: &mut Framebuffer, start: Vector2, end: Vector2, thickness: f32) {
+    let direction = Vector2::new(end.x - start.x, end.y - start.y);
+    let length = (direction.x * direction.x + direction.y * direction.y).sqrt();
+    if length == 0.0 || thickness <= 1.0 {
+        line(framebuffer, start, end);
+        return;
+    }
+
+    // Perpendicular a la línea, de medio grosor
+    let half = thickness / 2.0;
+    let normal = Vector2::new(-direction.y / length * half, direction.x / length * half);
+    fill_polygon(
+        framebuffer,
+        &[
+            Vector2::new(start.x + normal.x, start.y + normal.y),
+            Vector2::new(end.x + normal.x, end.y + normal.y),
+            Vector2::new(end.x - normal.x, end.y - normal.y),
+            Vector2::new(start.x - normal.x, start.y - normal.y),
+        ],
+    );
+}
+
+// Puntos de un arco de `start_angle` a `end_angle` (radianes, en el sentido de los ángulos del juego)
+fn arc_points(center: Vector2, radius: f32, start_angle: f32, end_angle: f32) -> Vec<Vector2> {
+    // Un segmento cada ~2 píxeles de arco
+    let segments = (((end_angle - start_angle).abs() * radius / 2.0).ceil() as usize).clamp(1, 256);
+    (0..=segments)
+        .map(|i| {
+            let angle = start_angle + (end_angle - start_angle) * i as f32 / segments as f32;
+            Vector2::new(center.x + angle.cos() * radius, center.y + angle.sin() * radius)
+        })
+        .collect()
+}
+
+// Contorno de un círculo
+pub fn circle(framebuffer: &mut Framebuffer, center: Vector2, radius: f32) {
+    framebuffer.draw_circle_outline(center.x.round() as i32, center.y.round() as i32, radius.round() as i32);
+}
+
+// Círculo relleno
+pub fn fill_circle(framebuffer: &mut Framebuffer, center: Vector2, radius: f32) {
+    framebuffer.draw_circle(center.x.round() as i32, center.y.round() as i32, radius.round() as i32);
+}
+
+// Arco (solo la curva)
+pub fn arc(framebuffer: &mut Framebuffer, center: Vector2, radius: f32, start_angle: f32, end_angle: f32) {
+    for pair in arc_points(center, radius, start_angle, end_angle).windows(2) {
+        line(framebuffer, pair[0], pair[1]);
+    }
+}
+
+// Sector circular relleno (una porción de pastel), por ejemplo un cono de visión
+pub fn fill_sector(framebuffer: &mut Framebuffer, center: Vector2, radius: f32, start_angle: f32, end_angle: f32) {
+    let mut points = vec![center];
+    points.extend(arc_points(center, radius, start_angle, end_angle));
+    fill_polygon(framebuffer, &points);
+}
+
+// Triángulo relleno
+pub fn fill_triangle(framebuffer: &mut Framebuffer, a: Vector2, b: Vector2, c: Vector2) {
+    fill_polygon(framebuffer, &[a, b, c]);
+}
+
+// Contorno de un polígono (se cierra uniendo el último punto con el primero)
+pub fn polygon(framebuffer: &mut Framebuffer, points: &[Vector2]) {
+    for (i, &point) in points.iter().enumerate() {
+        line(framebuffer, point, points[(i + 1) % points.len()]);
+    }
+}
+
+// Polígono relleno por filas (regla par-impar, sirve también para polígonos cóncavos)
+// Se rellenan los píxeles cuyo centro queda dentro del polígono
+pub fn fill_polygon(framebuffer: &mut Framebuffer, points: &[Vector2]) {
+    if points.len() < 3 {
+        return;
+    }
+
+    let min_y = points.iter().map(|p| p.y).fold(f32::INFINITY, f32::min);
+    let max_y = points.iter().map(|p| p.y).fold(f32::NEG_INFINITY, f32::max);
+    let first_row = (min_y.round() as i32).max(0);
+    let last_row = (max_y.round() as i32).min(framebuffer.height as i32 - 1);
+
+    let mut crossings = Vec::new();
+    for row in first_row..=last_row {
+        let y = row as f32 + 0.5;
+
+        // Puntos donde cada lado cruza el centro de la fila
+        crossings.clear();
+        for (i, &a) in points.iter().enumerate() {
+            let b = points[(i + 1) % points.len()];
+            if (a.y <= y) != (b.y <= y) {
+                crossings.push(a.x + (y - a.y) * (b.x - a.x) / (b.y - a.y));
+            }
+        }
+        crossings.sort_by(|a, b| a.total_cmp(b));
+
+        for span in crossings.chunks_exact(2) {
+            let start = ((span[0] - 0.5).ceil() as i32).max(0);
+            let end = ((span[1] - 0.5).ceil() as i32).min(framebuffer.width as i32);
+            for x in start..end {
+                plot(framebuffer, x, row);
+            }
+        }
+    }
+}
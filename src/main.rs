@@ -2,118 +2,61 @@
 #![allow(unused_imports)]
 #![allow(dead_code)]
 
+mod capture;
 mod caster;
+mod decals;
+mod font;
 mod framebuffer;
+mod headless;
+mod hot_reload;
+mod input;
+mod level;
+mod lights;
 mod line;
 mod maze;
+mod palette;
 mod player;
+mod renderer;
 mod textures;
 mod sprites;
 mod visual_effects;
 mod screens;
+mod settings;
+mod worker_pool;
 
-use caster::{cast_ray, render3d};
+use capture::Capture;
+use caster::cast_ray;
+use font::{TextAlign, TextStyle};
 use framebuffer::Framebuffer;
-use line::line;
-use maze::{Maze, load_maze, find_player_start, check_goal_collision, check_collision_with_margin, find_activated_triggers};
+use hot_reload::FileWatcher;
+use input::{Controls, PlayerInput};
+use level::Level;
+use line::{fill_circle, line, line_aa};
+use maze::{Maze, Doors, is_open_cell, is_portal_char, nearest_passable_cell, find_player_start, check_goal_collision, check_collision_with_margin, find_activated_triggers};
+use palette::{apply_retro_mode, RetroMode};
 use player::Player;
-use textures::TextureManager;
-use sprites::{Sprite, draw_sprite, update_sprite_distances, update_sprite_ai};
-use visual_effects::{VisualEffects, apply_flashlight_effect};
+use renderer::{render_minimap, render_scene, Viewport, MINIMAP_SIZE};
+use textures::{TextureManager, TextureSampling};
+use sprites::{Sprite, SpriteState, update_sprite_distances, update_sprite_ai, SPRITE_COLLISION_MARGIN};
+use visual_effects::VisualEffects;
+use settings::{QualityPreset, RenderSettings};
 use screens::{ScreenManager, ScreenType, render_screen, handle_menu_input, handle_victory_input, MenuAction, VictoryAction};
 use raylib::prelude::*;
+use std::path::Path;
 use std::thread;
 use std::time::Duration;
 
-fn draw_menu_text(d: &mut RaylibDrawHandle, screen_manager: &screens::ScreenManager) {
-    let screen_width = 1300;
-    let screen_height = 900;
-    let center_x = screen_width / 2;
-    let center_y = screen_height / 2;
-
-    match screen_manager.get_menu_state() {
-        screens::MenuState::MainMenu => {
-            // Opciones del menú principal
-            let options = vec!["Comenzar", "Selector de Niveles", "Salir"];
-            let option_spacing = 80;
-            let start_y = center_y - 50;
-
-            for (i, option_text) in options.iter().enumerate() {
-                let y = start_y as i32 + (i as i32 * option_spacing as i32);
-                let is_selected = i == screen_manager.get_selected_option();
-                
-                // Dibujar flecha si está seleccionado
-                if is_selected {
-                    d.draw_text(">", center_x - 200, y, 40, Color::WHITE);
-                }
-                
-                // Dibujar texto de la opción
-                d.draw_text(option_text, center_x - 150, y, 40, Color::WHITE);
-            }
-
-            // Instrucciones
-            d.draw_text("Flechas: Navegar | Enter: Seleccionar", center_x - 200, screen_height - 100, 20, Color::GRAY);
-        }
-        screens::MenuState::LevelSelect => {
-            // Opciones de nivel
-            let levels = vec!["Nivel 1", "Nivel 2", "Nivel 3"];
-            let option_spacing = 80;
-            let start_y = center_y - 50;
-
-            for (i, level_text) in levels.iter().enumerate() {
-                let y = start_y as i32 + (i as i32 * option_spacing as i32);
-                let is_selected = (i + 1) == screen_manager.get_current_level();
-                
-                // Dibujar flecha si está seleccionado
-                if is_selected {
-                    d.draw_text(">", center_x - 200, y, 40, Color::WHITE);
-                }
-                
-                // Dibujar texto del nivel
-                d.draw_text(level_text, center_x - 150, y, 40, Color::WHITE);
-            }
-
-            // Instrucciones
-            d.draw_text("Flechas: Navegar | Enter: Seleccionar | ESC: Volver", center_x - 250, screen_height - 100, 20, Color::GRAY);
-        }
-    }
-}
-
-fn draw_victory_text(d: &mut RaylibDrawHandle, current_level: usize) {
-    let screen_width = 1300;
-    let screen_height = 900;
-    let center_x = screen_width / 2;
-    let center_y = screen_height / 2;
-
-    // Mensaje de victoria: "Room" + número de nivel
-    let room_text = format!("Room {}", current_level);
-    d.draw_text(&room_text, center_x - 100, center_y - 100, 80, Color::GREEN);
-
-    if current_level < 3 {
-        d.draw_text("Enter: Siguiente Nivel", center_x - 150, center_y + 50, 30, Color::YELLOW);
-    } else {
-        d.draw_text("¡Todos los niveles completados!", center_x - 250, center_y + 50, 30, Color::GOLD);
-    }
-
-    d.draw_text("ESC: Menú Principal", center_x - 150, center_y + 120, 30, Color::WHITE);
-}
-
 fn draw_cell(framebuffer: &mut Framebuffer, xo: usize, yo: usize, block_size: usize, cell: char) {
-    // Los triggers ('t', 's', 'c') son transparentes y no se dibujan
-    if cell == ' ' || cell == 't' || cell == 's' || cell == 'c' {
+    // Los triggers ('t', 's', 'c') y las celdas exteriores son transparentes y no se dibujan
+    if is_open_cell(cell) {
         return;
     }
 
-    framebuffer.set_current_color(Color::RED);
-
-    for x in xo..xo + block_size {
-        for y in yo..yo + block_size {
-            framebuffer.set_pixel(x as u32, y as u32);
-        }
-    }
+    framebuffer.set_current_color(if is_portal_char(cell) { Color::PURPLE } else { Color::RED });
+    framebuffer.draw_rectangle(xo as u32, yo as u32, block_size as u32, block_size as u32);
 }
 
-pub fn render_maze(framebuffer: &mut Framebuffer, maze: &Maze, block_size: usize, player: &Player, _texture_manager: &TextureManager) {
+pub fn render_maze(framebuffer: &mut Framebuffer, maze: &Maze, doors: &Doors, block_size: usize, player: &Player, _texture_manager: &TextureManager) {
     // Render 2D view
     for (row_index, row) in maze.iter().enumerate() {
         for (col_index, &cell) in row.iter().enumerate() {
@@ -124,190 +67,71 @@ pub fn render_maze(framebuffer: &mut Framebuffer, maze: &Maze, block_size: usize
         }
     }
 
-    // Draw player and FOV rays
-    framebuffer.set_current_color(Color::GREEN);
-    for dx in -2..=2 {
-        for dy in -2..=2 {
-            framebuffer.set_pixel(
-                (player.pos.x + dx as f32) as u32,
-                (player.pos.y + dy as f32) as u32,
-            );
-        }
-    }
-
-    // Cast FOV rays
+    // Cast FOV rays: una línea suavizada desde el jugador hasta cada impacto
     let num_rays = 100;
     for i in 0..num_rays {
         let current_ray = i as f32 / num_rays as f32;
         let a = player.a - (player.fov / 2.0) + (player.fov * current_ray);
-        cast_ray(framebuffer, maze, player, a, block_size, true);
+        let intersect = cast_ray(framebuffer, maze, doors, player, a, block_size, false);
+        line_aa(framebuffer, player.pos, Vector2::new(intersect.hit_x, intersect.hit_y));
     }
-}
-
-pub fn render_world(framebuffer: &mut Framebuffer, _player: &Player) {
-    framebuffer.set_current_color(Color::GRAY);
 
-    // Draw sky usando rectángulo (mucho más eficiente)
-    framebuffer.draw_rectangle(0, 0, framebuffer.width, framebuffer.height / 2);
+    // Draw player
+    framebuffer.set_current_color(Color::GREEN);
+    fill_circle(framebuffer, player.pos, 3.0);
+}
 
-    // Draw ground usando rectángulo
-    framebuffer.draw_rectangle(0, framebuffer.height / 2, framebuffer.width, framebuffer.height / 2);
+// Jugadores en juego: el primero y, en pantalla dividida, el segundo
+fn active_players<'a>(player: &'a Player, second_player: &'a Option<Player>) -> Vec<&'a Player> {
+    std::iter::once(player).chain(second_player.as_ref()).collect()
 }
 
-pub fn render_minimap(framebuffer: &mut Framebuffer, maze: &Maze, player: &Player, sprites: &[sprites::Sprite], block_size: usize) {
-    // Tamaño del minimapa
-    let minimap_size = 280u32;
-    let minimap_scale = 3u32; // Escala: cada celda del laberinto será de 3x3 píxeles en el minimapa
-    
-    // Posición en la esquina superior derecha
-    let minimap_x = framebuffer.width - minimap_size - 10;
-    let minimap_y = 10;
-    
-    // Calcular el offset para centrar el minimapa en la posición del jugador
-    let player_grid_x = (player.pos.x / block_size as f32) as i32;
-    let player_grid_y = (player.pos.y / block_size as f32) as i32;
-    
-    // Calcular el rango visible del laberinto
-    let visible_cells = (minimap_size / minimap_scale) as i32;
-    let start_grid_x = (player_grid_x - visible_cells / 2).max(0);
-    let start_grid_y = (player_grid_y - visible_cells / 2).max(0);
-    let end_grid_x = (start_grid_x + visible_cells).min(maze[0].len() as i32);
-    let end_grid_y = (start_grid_y + visible_cells).min(maze.len() as i32);
-    
-    // Dibujar el laberinto en el minimapa
-    for (row_index, row) in maze.iter().enumerate() {
-        let grid_y = row_index as i32;
-        if grid_y < start_grid_y || grid_y >= end_grid_y {
-            continue;
-        }
-        
-        for (col_index, &cell) in row.iter().enumerate() {
-            let grid_x = col_index as i32;
-            if grid_x < start_grid_x || grid_x >= end_grid_x {
-                continue;
-            }
-            
-            // Los triggers ('t', 's', 'c') son transparentes y no se dibujan en el minimapa
-            if cell != ' ' && cell != 't' && cell != 's' && cell != 'c' {
-                // Dibujar muro en el minimapa
-                let minimap_cell_x = minimap_x + ((grid_x - start_grid_x) * minimap_scale as i32) as u32;
-                let minimap_cell_y = minimap_y + ((grid_y - start_grid_y) * minimap_scale as i32) as u32;
-                
-                framebuffer.set_current_color(Color::RED);
-                for py in 0..minimap_scale {
-                    for px in 0..minimap_scale {
-                        if minimap_cell_x + px < framebuffer.width && minimap_cell_y + py < framebuffer.height {
-                            framebuffer.set_pixel(minimap_cell_x + px, minimap_cell_y + py);
-                        }
-                    }
-                }
-            }
-        }
+// Archivos que vigila el modo de desarrollo: el laberinto, la paleta del nivel y las texturas
+fn watch_level_files(watcher: &mut FileWatcher, level_file: &str, level: &Level, texture_manager: &TextureManager) {
+    watcher.clear();
+    watcher.watch(level_file);
+    if let Some(mode) = &level.retro_mode {
+        watcher.watch(&mode.palette.name); // las paletas incorporadas no son archivos y nunca cambian
     }
-    
-    // Dibujar el jugador en el minimapa
-    let player_minimap_x = minimap_x + ((player_grid_x - start_grid_x) * minimap_scale as i32) as u32;
-    let player_minimap_y = minimap_y + ((player_grid_y - start_grid_y) * minimap_scale as i32) as u32;
-    
-    framebuffer.set_current_color(Color::GREEN);
-    // Dibujar un pequeño círculo o cuadrado para el jugador
-    for dy in -1..=1 {
-        for dx in -1..=1 {
-            let px = (player_minimap_x as i32 + dx).max(0) as u32;
-            let py = (player_minimap_y as i32 + dy).max(0) as u32;
-            if px < framebuffer.width && py < framebuffer.height {
-                framebuffer.set_pixel(px, py);
-            }
-        }
+    for path in texture_manager.source_files() {
+        watcher.watch(path);
     }
-    
-    // Dibujar sprites y su FOV en el minimapa
-    for sprite in sprites {
-        let sprite_grid_x = (sprite.pos.x / block_size as f32) as i32;
-        let sprite_grid_y = (sprite.pos.y / block_size as f32) as i32;
-        
-        // Solo dibujar si el sprite está en el área visible del minimapa
-        if sprite_grid_x >= start_grid_x && sprite_grid_x < end_grid_x &&
-           sprite_grid_y >= start_grid_y && sprite_grid_y < end_grid_y {
-            
-            let sprite_minimap_x = minimap_x + ((sprite_grid_x - start_grid_x) * minimap_scale as i32) as u32;
-            let sprite_minimap_y = minimap_y + ((sprite_grid_y - start_grid_y) * minimap_scale as i32) as u32;
-            
-            // Dibujar FOV del sprite (cono de visión) - Optimizado: menos líneas
-            if sprite.player_detected {
-                framebuffer.set_current_color(Color::new(255, 100, 100, 150)); // Rojo claro cuando detecta
-            } else {
-                framebuffer.set_current_color(Color::new(100, 100, 255, 100)); // Azul claro cuando no detecta
-            }
-            
-            // Dibujar líneas del FOV (cono) - Optimizado: reducir a 3 líneas
-            let fov_half = sprite.fov / 2.0;
-            let fov_range = 15.0; // Rango del FOV en el minimapa
-            let num_lines = 3; // Reducido de 5 a 3 para mejor rendimiento
-            
-            for i in 0..=num_lines {
-                let angle_offset = -fov_half + (fov_half * 2.0 * i as f32 / num_lines as f32);
-                let angle = sprite.facing_angle + angle_offset;
-                let end_x = sprite_minimap_x as f32 + angle.cos() * fov_range;
-                let end_y = sprite_minimap_y as f32 + angle.sin() * fov_range;
-                
-                // Dibujar línea usando bresenham o píxeles simples - Optimizado: menos pasos
-                let steps = (fov_range as i32 / 2).max(3); // Reducido a la mitad
-                for step in 0..=steps {
-                    let t = step as f32 / steps as f32;
-                    let x = sprite_minimap_x as f32 + (end_x - sprite_minimap_x as f32) * t;
-                    let y = sprite_minimap_y as f32 + (end_y - sprite_minimap_y as f32) * t;
-                    
-                    let px = x as i32;
-                    let py = y as i32;
-                    
-                    if px >= 0 && px < framebuffer.width as i32 &&
-                       py >= 0 && py < framebuffer.height as i32 {
-                        framebuffer.set_pixel(px as u32, py as u32);
-                    }
-                }
-            }
-            
-            // Dibujar el sprite en el minimapa
-            if sprite.player_detected {
-                framebuffer.set_current_color(Color::RED); // Rojo cuando detecta al jugador
-            } else {
-                framebuffer.set_current_color(Color::BLUE); // Azul cuando no detecta
-            }
-            
-            // Dibujar un pequeño cuadrado para el sprite
-            for dy in -1..=1 {
-                for dx in -1..=1 {
-                    let px = (sprite_minimap_x as i32 + dx).max(0) as u32;
-                    let py = (sprite_minimap_y as i32 + dy).max(0) as u32;
-                    if px < framebuffer.width && py < framebuffer.height {
-                        framebuffer.set_pixel(px, py);
-                    }
-                }
-            }
-            
-            // Dibujar dirección del sprite (línea pequeña)
-            framebuffer.set_current_color(Color::YELLOW);
-            let dir_length = 4.0;
-            let dir_end_x = sprite_minimap_x as f32 + sprite.facing_angle.cos() * dir_length;
-            let dir_end_y = sprite_minimap_y as f32 + sprite.facing_angle.sin() * dir_length;
-            
-            let dir_steps = 4;
-            for step in 0..=dir_steps {
-                let t = step as f32 / dir_steps as f32;
-                let x = sprite_minimap_x as f32 + (dir_end_x - sprite_minimap_x as f32) * t;
-                let y = sprite_minimap_y as f32 + (dir_end_y - sprite_minimap_y as f32) * t;
-                
-                let px = x as i32;
-                let py = y as i32;
-                
-                if px >= 0 && px < framebuffer.width as i32 &&
-                   py >= 0 && py < framebuffer.height as i32 {
-                    framebuffer.set_pixel(px as u32, py as u32);
-                }
-            }
-        }
+}
+
+// Al recargar el nivel, el jugador y los sprites se quedan donde estaban si esa posición sigue libre
+// (con las mismas reglas que al moverse: puertas abiertas y portales incluidos); si ahora chocan
+// con una pared pasan al centro de la celda transitable más cercana.
+// Devuelve false si el laberinto ya no tiene ninguna celda transitable
+fn keep_in_level(pos: &mut Vector2, level: &Level, block_size: usize, margin: f32) -> bool {
+    if !check_collision_with_margin(&level.maze, &level.doors, pos.x, pos.y, block_size, margin) {
+        return true;
     }
+    let block_size_f = block_size as f32;
+    let col = (pos.x / block_size_f).floor() as i32;
+    let row = (pos.y / block_size_f).floor() as i32;
+    let Some((col, row)) = nearest_passable_cell(&level.maze, &level.doors, col, row) else {
+        return false;
+    };
+    *pos = Vector2::new((col as f32 + 0.5) * block_size_f, (row as f32 + 0.5) * block_size_f);
+    true
+}
+
+fn keep_player_in_level(player: &mut Player, level: &Level, block_size: usize) {
+    const COLLISION_MARGIN: f32 = 15.0;
+    keep_in_level(&mut player.pos, level, block_size, COLLISION_MARGIN);
+}
+
+// Los sprites sin ninguna celda transitable a donde ir desaparecen
+fn keep_sprites_in_level(sprites: &mut Vec<Sprite>, level: &Level, block_size: usize) {
+    sprites.retain_mut(|sprite| keep_in_level(&mut sprite.pos, level, block_size, SPRITE_COLLISION_MARGIN));
+}
+
+// Un framebuffer de escena por vista
+fn build_scene_framebuffers(viewports: &[Viewport], render_settings: &RenderSettings, window_width: u32, window_height: u32) -> Vec<Framebuffer> {
+    viewports
+        .iter()
+        .map(|viewport| viewport.scene_framebuffer(render_settings, window_width, window_height))
+        .collect()
 }
 
 fn main() {
@@ -315,6 +139,15 @@ fn main() {
     let window_height = 900;
     let block_size = 100;
 
+    // Render sin ventana de una cámara fija (pruebas de imágenes de referencia)
+    let args: Vec<String> = std::env::args().collect();
+    if args.get(1).map(String::as_str) == Some("--headless") {
+        std::process::exit(headless::run(&args[2..]));
+    }
+
+    // Modo de desarrollo: recargar el nivel, las texturas y la paleta al guardarlos
+    let dev_mode = args.iter().any(|arg| arg == "--dev");
+
     let (mut window, raylib_thread) = raylib::init()
         .size(window_width, window_height)
         .title("Maze Game")
@@ -330,8 +163,14 @@ fn main() {
 
     framebuffer.set_background_color(Color::new(50, 50, 100, 255));
 
+    // La vista 3D se dibuja a la resolución interna del preset y luego se escala a la ventana
+    let mut render_settings = RenderSettings::from_preset(QualityPreset::High, window_width as u32, window_height as u32);
+    // Una vista por jugador: toda la ventana, o mitades lado a lado con dos jugadores (F6)
+    let mut viewports = vec![Viewport::full(window_width as u32, window_height as u32)];
+    let mut scene_framebuffers = build_scene_framebuffers(&viewports, &render_settings, window_width as u32, window_height as u32);
+
     // Initialize texture manager
-    let texture_manager = TextureManager::new(&mut window, &raylib_thread);
+    let mut texture_manager = TextureManager::new(&mut window, &raylib_thread);
 
     // Initialize screen manager
     let mut screen_manager = ScreenManager::new(&mut window, &raylib_thread);
@@ -365,12 +204,12 @@ fn main() {
 
     // Cargar sonidos de triggers
     let mut trigger_sounds: Vec<Option<raylib::ffi::Sound>> = vec![None, None, None];
-    let sound_paths = vec!["assets/STAB_01.mp3", "assets/STAB_02.mp3", "assets/STAB_03.mp3"];
+    let sound_paths = ["assets/STAB_01.mp3", "assets/STAB_02.mp3", "assets/STAB_03.mp3"];
     
     for (i, path) in sound_paths.iter().enumerate() {
         unsafe {
             let c_path = std::ffi::CString::new(*path)
-                .expect(&format!("Error al crear CString para {}", path));
+                .unwrap_or_else(|_| panic!("Error al crear CString para {}", path));
             let sound = raylib::ffi::LoadSound(c_path.as_ptr());
             if sound.frameCount > 0 {
                 trigger_sounds[i] = Some(sound);
@@ -381,17 +220,29 @@ fn main() {
         }
     }
 
+    // Capturas de pantalla; las secuencias guardan uno de cada 2 frames (30 por segundo)
+    let mut capture = Capture::new("capturas", 2);
+
     // Game state
     let mut game_state = GameState::MainMenu;
     let mut current_level = 1;
-    let mut maze = load_maze("maze.txt");
+    let mut level = Level::load("maze.txt", block_size).unwrap_or_else(|error| panic!("No se pudo cargar maze.txt: {}", error));
+    let mut post_pipeline = level.post_pipeline(&render_settings);
+    let mut retro_mode = level.retro_mode.clone();
+
+    // Revisar los archivos dos veces por segundo (solo con --dev)
+    let mut file_watcher = dev_mode.then(|| FileWatcher::new(Duration::from_millis(500)));
+    if let Some(watcher) = &mut file_watcher {
+        watch_level_files(watcher, "maze.txt", &level, &texture_manager);
+    }
 
     // Create player instance starting at a reasonable position
-    let mut player = Player {
-        pos: Vector2::new(150.0, 150.0),
-        a: 0.0,
-        fov: std::f32::consts::PI * 2.0 / 3.0, // 60 degrees field of view
-    };
+    const PLAYER_FOV: f32 = std::f32::consts::PI * 2.0 / 3.0;
+    const SCARE_BLACKOUT: f32 = 4.0; // Segundos que quedan apagadas las luces tras un susto
+    let mut player = Player::new(150.0, 150.0, 0.0, PLAYER_FOV);
+
+    // Segundo jugador local (pantalla dividida); se controla con un gamepad o con I/J/K/L
+    let mut second_player: Option<Player> = None;
 
     // Inicializar sprites vacío - solo se crearán cuando se activen triggers
     let mut sprites = vec![];
@@ -401,7 +252,7 @@ fn main() {
     let mut activated_triggers: HashSet<(i32, i32)> = HashSet::new();
 
     // Inicializar efectos visuales
-    let visual_effects = VisualEffects::new();
+    let mut visual_effects = VisualEffects::new();
 
     let mut mode = "3D";
     let mut mouse_rotation_enabled = true; // Habilitar rotación con mouse por defecto
@@ -459,28 +310,44 @@ fn main() {
                 // Manejar input del menú
                 if let Some(action) = handle_menu_input(&window, &mut screen_manager) {
                     match action {
-                        MenuAction::StartLevel(level) => {
-                            current_level = level;
-                            let maze_file = screen_manager.get_level_file(level);
-                            maze = load_maze(maze_file);
-                            
-                            // Encontrar posición inicial del jugador
-                            if let Some((x, y)) = find_player_start(&maze) {
-                                player.pos = Vector2::new(x, y);
-                                player.a = 0.0;
+                        MenuAction::StartLevel(level_number) => {
+                            let maze_file = screen_manager.get_level_file(level_number);
+                            match Level::load(maze_file, block_size) {
+                                Err(error) => eprintln!("Advertencia: No se pudo cargar {}: {}", maze_file, error),
+                                Ok(new_level) => {
+                                    current_level = level_number;
+                                    level = new_level;
+                                    texture_manager.load_level_textures(maze_file);
+                                    texture_manager.upload_textures(&mut window, &raylib_thread);
+                                    if let Some(watcher) = &mut file_watcher {
+                                        watch_level_files(watcher, maze_file, &level, &texture_manager);
+                                    }
+                                    post_pipeline = level.post_pipeline(&render_settings);
+                                    retro_mode = level.retro_mode.clone();
+                                    
+                                    // Encontrar posición inicial del jugador
+                                    if let Some((x, y)) = find_player_start(&level.maze) {
+                                        player.pos = Vector2::new(x, y);
+                                        player.a = 0.0;
+                                        if let Some(second) = &mut second_player {
+                                            second.pos = player.pos;
+                                            second.a = 0.0;
+                                        }
+                                    }
+                                    
+                                    // Resetear triggers activados al cambiar de nivel
+                                    activated_triggers.clear();
+                                    
+                                    // Resetear sprites (vaciar lista)
+                                    sprites = vec![];
+                                    
+                                    screen_manager.set_current_level(level_number);
+                                    screen_manager.set_menu_state(screens::MenuState::MainMenu);
+                                    screen_manager.set_selected_option(0);
+                                    game_state = GameState::Playing;
+                                    window.hide_cursor();
+                                }
                             }
-                            
-                            // Resetear triggers activados al cambiar de nivel
-                            activated_triggers.clear();
-                            
-                            // Resetear sprites (vaciar lista)
-                            sprites = vec![];
-                            
-                            screen_manager.set_current_level(level);
-                            screen_manager.set_menu_state(screens::MenuState::MainMenu);
-                            screen_manager.set_selected_option(0);
-                            game_state = GameState::Playing;
-                            window.hide_cursor();
                         }
                         MenuAction::Exit => {
                             // Salir del juego
@@ -493,14 +360,6 @@ fn main() {
                 // Renderizar menú
                 framebuffer.clear();
                 render_screen(&mut framebuffer, &mut window, &raylib_thread, ScreenType::MainMenu, &screen_manager);
-                
-                // Dibujar framebuffer y texto en una sola operación
-                if let Ok(texture) = window.load_texture_from_image(&raylib_thread, &framebuffer.color_buffer) {
-                    let mut d = window.begin_drawing(&raylib_thread);
-                    d.clear_background(Color::BLACK);
-                    d.draw_texture(&texture, 0, 0, Color::WHITE);
-                    draw_menu_text(&mut d, &screen_manager);
-                }
             }
             
             GameState::Victory => {
@@ -509,24 +368,40 @@ fn main() {
                     match action {
                         VictoryAction::NextLevel => {
                             if current_level < 3 {
-                                current_level += 1;
-                                let maze_file = screen_manager.get_level_file(current_level);
-                                maze = load_maze(maze_file);
-                                
-                                if let Some((x, y)) = find_player_start(&maze) {
-                                    player.pos = Vector2::new(x, y);
-                                    player.a = 0.0;
+                                let maze_file = screen_manager.get_level_file(current_level + 1);
+                                match Level::load(maze_file, block_size) {
+                                    Err(error) => eprintln!("Advertencia: No se pudo cargar {}: {}", maze_file, error),
+                                    Ok(new_level) => {
+                                        current_level += 1;
+                                        level = new_level;
+                                        texture_manager.load_level_textures(maze_file);
+                                        texture_manager.upload_textures(&mut window, &raylib_thread);
+                                        if let Some(watcher) = &mut file_watcher {
+                                            watch_level_files(watcher, maze_file, &level, &texture_manager);
+                                        }
+                                        post_pipeline = level.post_pipeline(&render_settings);
+                                        retro_mode = level.retro_mode.clone();
+                                        
+                                        if let Some((x, y)) = find_player_start(&level.maze) {
+                                            player.pos = Vector2::new(x, y);
+                                            player.a = 0.0;
+                                            if let Some(second) = &mut second_player {
+                                                second.pos = player.pos;
+                                                second.a = 0.0;
+                                            }
+                                        }
+                                        
+                                        // Resetear triggers activados al cambiar de nivel
+                                        activated_triggers.clear();
+                                        
+                                        // Resetear sprites (vaciar lista)
+                                        sprites = vec![];
+                                        
+                                        screen_manager.set_current_level(current_level);
+                                        game_state = GameState::Playing;
+                                        window.hide_cursor();
+                                    }
                                 }
-                                
-                                // Resetear triggers activados al cambiar de nivel
-                                activated_triggers.clear();
-                                
-                                // Resetear sprites (vaciar lista)
-                                sprites = vec![];
-                                
-                                screen_manager.set_current_level(current_level);
-                                game_state = GameState::Playing;
-                                window.hide_cursor();
                             } else {
                                 // Todos los niveles completados
                                 game_state = GameState::MainMenu;
@@ -544,19 +419,14 @@ fn main() {
                 // Renderizar pantalla de victoria
                 framebuffer.clear();
                 render_screen(&mut framebuffer, &mut window, &raylib_thread, ScreenType::Victory, &screen_manager);
-                
-                // Dibujar framebuffer y texto en una sola operación
-                if let Ok(texture) = window.load_texture_from_image(&raylib_thread, &framebuffer.color_buffer) {
-                    let mut d = window.begin_drawing(&raylib_thread);
-                    d.clear_background(Color::BLACK);
-                    d.draw_texture(&texture, 0, 0, Color::WHITE);
-                    draw_victory_text(&mut d, current_level);
-                }
             }
             
             GameState::Playing => {
-                // Verificar colisión con la meta (g)
-                if check_goal_collision(&maze, player.pos.x, player.pos.y, block_size) {
+                // Verificar colisión con la meta (g): gana el primero que llegue
+                let reached_goal = active_players(&player, &second_player)
+                    .iter()
+                    .any(|p| check_goal_collision(&level.maze, p.pos.x, p.pos.y, block_size));
+                if reached_goal {
                     game_state = GameState::Victory;
                     window.show_cursor();
                     continue;
@@ -587,11 +457,80 @@ fn main() {
                     continue;
                 }
                 
-                // 1. Process player movement
-                process_events(&window, &mut player, &maze, mode == "3D" && mouse_rotation_enabled);
+                // 1. Process player movement: cada jugador con sus controles
+                let mut controlled: Vec<(&mut Player, Controls)> = vec![(&mut player, Controls::KeyboardMouse)];
+                if let Some(second) = second_player.as_mut() {
+                    controlled.push((second, Controls::second_player(&window)));
+                }
+                for (player, controls) in controlled {
+                    let input = PlayerInput::read(&window, controls, mode == "3D" && mouse_rotation_enabled);
+                    process_events(player, &input, &level.maze, &level.doors, window.get_frame_time());
+
+                    // Al entrar en un portal, salir por su pareja
+                    if let Some((x, y, a)) = level.portals.teleport(player.pos.x, player.pos.y, player.a, block_size) {
+                        player.pos.x = x;
+                        player.pos.y = y;
+                        player.a = a;
+                    }
+
+                    // 1.1. Abrir/cerrar puertas cercanas (tecla E, o la de puertas del jugador)
+                    if input.use_door {
+                        level.doors.toggle_near(player.pos.x, player.pos.y, block_size);
+                    }
+                }
+                level.doors.update(window.get_frame_time());
+
+                // 1.15. Modo de desarrollo: recargar los archivos que cambiaron en disco
+                if let Some(watcher) = &mut file_watcher {
+                    let changed = watcher.poll();
+                    if !changed.is_empty() {
+                        let maze_file = screen_manager.get_level_file(current_level);
+                        let palette_changed = level
+                            .retro_mode
+                            .as_ref()
+                            .is_some_and(|mode| changed.iter().any(|path| path == Path::new(&mode.palette.name)));
+                        let level_changed = palette_changed || changed.iter().any(|path| path == Path::new(maze_file));
+
+                        texture_manager.reload_files(&changed);
+                        if level_changed {
+                            // Las luces y los efectos vuelven a su estado inicial; las puertas que siguen en su
+                            // lugar conservan su apertura y los jugadores y los sprites se quedan donde están
+                            // si siguen en una celda transitable.
+                            // Un archivo a medio editar no debe cerrar el juego: se sigue con el nivel anterior
+                            match Level::load(maze_file, block_size) {
+                                Err(error) => eprintln!("Advertencia: No se pudo recargar {}, se mantiene el nivel actual: {}", maze_file, error),
+                                Ok(mut new_level) => {
+                                    new_level.doors.keep_state_from(&level.doors);
+                                    level = new_level;
+                                    texture_manager.load_level_textures(maze_file);
+                                    post_pipeline = level.post_pipeline(&render_settings);
+                                    retro_mode = level.retro_mode.clone();
+                                    keep_player_in_level(&mut player, &level, block_size);
+                                    if let Some(second) = &mut second_player {
+                                        keep_player_in_level(second, &level, block_size);
+                                    }
+                                    keep_sprites_in_level(&mut sprites, &level, block_size);
+                                }
+                            }
+                        }
+                        // Las texturas GPU se vuelven a subir con los texels recargados
+                        texture_manager.upload_textures(&mut window, &raylib_thread);
+                        watch_level_files(watcher, maze_file, &level, &texture_manager);
+                        for path in &changed {
+                            println!("Recargado: {}", path.display());
+                        }
+                    }
+                }
 
-                // 1.25. Verificar triggers activados y crear sprites temporales
-                let activated = find_activated_triggers(&maze, player.pos.x, player.pos.y, block_size);
+                // 1.2. Parpadeo y estado de las luces, y cuadro actual de las texturas animadas
+                level.light_map.update(window.get_time() as f32);
+                texture_manager.update(window.get_time() as f32);
+
+                // 1.25. Verificar triggers activados (por cualquier jugador) y crear sprites temporales
+                let activated: Vec<(f32, f32, char)> = active_players(&player, &second_player)
+                    .iter()
+                    .flat_map(|p| find_activated_triggers(&level.maze, p.pos.x, p.pos.y, block_size))
+                    .collect();
                 for (trigger_x, trigger_y, trigger_char) in activated {
                     // Convertir posición a coordenadas de grid para usar como clave única
                     let grid_x = (trigger_x / block_size as f32) as i32;
@@ -619,13 +558,27 @@ fn main() {
                             }
                         }
                         
+                        // Los sustos apagan las luces cercanas durante un rato y ponen nervioso al jugador
+                        level.light_map.blackout_near(trigger_x, trigger_y, block_size as f32 * 3.0, window.get_time() as f32, SCARE_BLACKOUT);
+                        visual_effects.trigger_anxiety();
+
                         // Marcar este trigger como activado
                         activated_triggers.insert((grid_x, grid_y));
                     }
                 }
 
-                // 1.5. Update sprite AI (persecución del jugador)
-                update_sprite_ai(&mut sprites, &player, &maze, block_size);
+                // 1.5. Update sprite AI (persecución del jugador más cercano)
+                update_sprite_ai(&mut sprites, &active_players(&player, &second_player), &level.maze, &level.doors, &level.portals, block_size, window.get_frame_time());
+
+                // 1.6. Efectos de pantalla: la ansiedad dura mientras un sprite persigue a un jugador
+                // y el daño mientras uno lo ataca; al terminar se desvanecen solos
+                visual_effects.update(window.get_frame_time());
+                if sprites.iter().any(|sprite| sprite.player_detected) {
+                    visual_effects.trigger_anxiety();
+                }
+                if sprites.iter().any(|sprite| sprite.state == SpriteState::Attack) {
+                    visual_effects.trigger_damage();
+                }
 
                 // 2. clear framebuffer
                 framebuffer.clear();
@@ -637,43 +590,124 @@ fn main() {
                     thread::sleep(Duration::from_millis(200));
                 }
 
+                // Cambiar preset de calidad (tecla F2)
+                if window.is_key_pressed(KeyboardKey::KEY_F2) {
+                    render_settings = RenderSettings::from_preset(render_settings.preset.next(), window_width as u32, window_height as u32);
+                    post_pipeline = level.post_pipeline(&render_settings);
+                    scene_framebuffers = build_scene_framebuffers(&viewports, &render_settings, window_width as u32, window_height as u32);
+                }
+
+                // Pantalla dividida con un segundo jugador (tecla F6); aparece donde está el primero
+                if window.is_key_pressed(KeyboardKey::KEY_F6) {
+                    second_player = match second_player {
+                        Some(_) => None,
+                        None => Some(Player::new(player.pos.x, player.pos.y, player.a, PLAYER_FOV)),
+                    };
+                    let view_count = if second_player.is_some() { 2 } else { 1 };
+                    viewports = Viewport::split(window_width as u32, window_height as u32, view_count);
+                    scene_framebuffers = build_scene_framebuffers(&viewports, &render_settings, window_width as u32, window_height as u32);
+                    player.fov = viewports[0].field_of_view(PLAYER_FOV, window_width as u32);
+                    if let Some(second) = &mut second_player {
+                        second.fov = viewports[1].field_of_view(PLAYER_FOV, window_width as u32);
+                    }
+                }
+
+                // Alternar la niebla (tecla F4)
+                if window.is_key_pressed(KeyboardKey::KEY_F4) {
+                    post_pipeline.toggle("fog");
+                }
+
+                // Cambiar la paleta retro (tecla F5): colores normales, CGA, Game Boy...
+                if window.is_key_pressed(KeyboardKey::KEY_F5) {
+                    retro_mode = RetroMode::next(retro_mode.as_ref());
+                    match &retro_mode {
+                        Some(mode) => println!("Paleta: {}", mode.name()),
+                        None => println!("Paleta: colores normales"),
+                    }
+                }
+
+                // Alternar el filtrado bilineal de las texturas (tecla F3)
+                if window.is_key_pressed(KeyboardKey::KEY_F3) {
+                    render_settings.texture_sampling = match render_settings.texture_sampling {
+                        TextureSampling::Nearest => TextureSampling::Bilinear,
+                        TextureSampling::Bilinear => TextureSampling::Nearest,
+                    };
+                }
+
                 // 3. Render based on mode
                 if mode == "2D" {
-                    render_maze(&mut framebuffer, &maze, block_size, &player, &texture_manager);
+                    render_maze(&mut framebuffer, &level.maze, &level.doors, block_size, &player, &texture_manager);
+                    if let Some(second) = &second_player {
+                        framebuffer.set_current_color(Color::SKYBLUE);
+                        fill_circle(&mut framebuffer, second.pos, 3.0);
+                    }
                 } else {
-                    render_world(&mut framebuffer, &player);
-                    let mut z_buffer = render3d(&mut framebuffer, &player, &texture_manager, &maze);
-                    
-                    // Actualizar distancias de sprites y dibujarlos
-                    update_sprite_distances(&mut sprites, &player);
-                    for sprite in &sprites {
-                        draw_sprite(&mut framebuffer, &player, sprite, &texture_manager, &mut z_buffer);
+                    // Cada jugador en su vista: actualizar distancias de sprites, dibujar la escena
+                    // y escalarla a su región de la ventana
+                    let views = viewports.iter().zip(scene_framebuffers.iter_mut());
+                    for ((viewport, scene_framebuffer), view_player) in views.zip(active_players(&player, &second_player)) {
+                        update_sprite_distances(&mut sprites, view_player);
+                        render_scene(scene_framebuffer, view_player, &level, &sprites, &texture_manager, &render_settings, &post_pipeline, &visual_effects, window.get_time() as f32);
+                        viewport.present(&mut framebuffer, scene_framebuffer, render_settings.upscale_filter);
+                    }
+
+                    // Separador entre las vistas
+                    framebuffer.set_current_color(Color::BLACK);
+                    for viewport in viewports.iter().skip(1) {
+                        framebuffer.draw_rectangle(viewport.x.saturating_sub(1), viewport.y, 2, viewport.height);
                     }
-                    
-                    // Aplicar efecto de linterna
-                    apply_flashlight_effect(&mut framebuffer, &player, &visual_effects);
                 }
                 
-                // Renderizar minimapa en ambos modos
-                render_minimap(&mut framebuffer, &maze, &player, &sprites, block_size);
+                // Renderizar el minimapa de cada jugador en ambos modos
+                for (viewport, view_player) in viewports.iter().zip(active_players(&player, &second_player)) {
+                    render_minimap(&mut framebuffer, viewport, &level.maze, view_player, &sprites, block_size);
+                }
+
+                // Paleta retro sobre el frame final (el texto del HUD queda con sus colores)
+                if let Some(mode) = &retro_mode {
+                    apply_retro_mode(&mut framebuffer, mode);
+                }
 
                 // 4. swap buffers y dibujar FPS
-                if let Ok(texture) = window.load_texture_from_image(&raylib_thread, &framebuffer.color_buffer) {
-                    let mut d = window.begin_drawing(&raylib_thread);
-                    d.clear_background(Color::BLACK);
-                    d.draw_texture(&texture, 0, 0, Color::WHITE);
-                    
-                    // Dibujar FPS debajo del minimapa con indicador de modo
-                    let minimap_size = 280u32;
-                    let minimap_x = framebuffer.width - minimap_size - 10;
-                    let minimap_y = 10;
-                    let fps_y = minimap_y + minimap_size + 5;
-                    let fps_text = format!("FPS: {} ({})", fps, mode);
-                    d.draw_text(&fps_text, minimap_x as i32, fps_y as i32, 20, Color::WHITE);
+                // Dibujar FPS debajo del minimapa con indicador de modo (en las otras vistas, el número de jugador)
+                let fps_style = TextStyle::new(2, Color::WHITE).aligned(TextAlign::Right).outlined(Color::BLACK);
+                for (index, viewport) in viewports.iter().enumerate() {
+                    let text = if index == 0 {
+                        format!("FPS: {} ({}) Calidad: {}", fps, mode, render_settings.preset.name())
+                    } else {
+                        format!("Jugador {}", index + 1)
+                    };
+                    let text_x = (viewport.x + viewport.width) as i32 - 10;
+                    let text_y = (viewport.y + 10 + MINIMAP_SIZE + 5) as i32;
+                    framebuffer.draw_text(screen_manager.font(), &text, text_x, text_y, &fps_style);
                 }
+            }
+        }
 
+        // Capturas del frame compuesto: F9 guarda una imagen, F10 empieza o termina una secuencia
+        if window.is_key_pressed(KeyboardKey::KEY_F9) {
+            match capture.screenshot(&framebuffer) {
+                Ok(path) => println!("Captura guardada en {}", path.display()),
+                Err(error) => eprintln!("Advertencia: No se pudo guardar la captura: {}", error),
             }
         }
+        if window.is_key_pressed(KeyboardKey::KEY_F10) {
+            match capture.toggle_recording() {
+                Ok(Some(directory)) => println!("Grabando secuencia en {}", directory.display()),
+                Ok(None) => println!("Secuencia terminada"),
+                Err(error) => eprintln!("Advertencia: No se pudo empezar la secuencia: {}", error),
+            }
+        }
+        capture.record_frame(&framebuffer);
+
+        // Indicador de grabación (después de capturar, para que no salga en los frames)
+        if capture.is_recording() {
+            framebuffer.set_current_color(Color::RED);
+            framebuffer.draw_circle(20, 20, 8);
+        }
+
+        // Dibujar el framebuffer en la ventana
+        framebuffer.swap_buffer(&mut window, &raylib_thread);
         
         // Control de FPS a 60 FPS estables (al final de cada iteración del loop)
         let frame_elapsed = frame_start_time.elapsed();
@@ -683,52 +717,36 @@ fn main() {
     }
 }
 
-pub fn process_events(window: &RaylibHandle, player: &mut Player, maze: &Maze, enable_mouse_rotation: bool) {
+pub fn process_events(player: &mut Player, input: &PlayerInput, maze: &Maze, doors: &Doors, delta_time: f32) {
     const MOVE_SPEED: f32 = 10.0;
-    const ROTATION_SPEED: f32 = std::f32::consts::PI / 10.0;
-    const MOUSE_SENSITIVITY: f32 = 0.003; // Sensibilidad del mouse para rotación
+    const CROUCH_SPEED_FACTOR: f32 = 0.5; // Agachado se camina más lento
     const BLOCK_SIZE: usize = 100;
     const COLLISION_MARGIN: f32 = 15.0; // Margen de seguridad para evitar pegarse a las paredes
 
-    // Rotación con mouse (solo horizontal)
-    if enable_mouse_rotation {
-        let mouse_delta = window.get_mouse_delta();
-        let rotation_delta = mouse_delta.x as f32 * MOUSE_SENSITIVITY;
-        player.a += rotation_delta;
-    }
+    // Rotación e inclinación de la vista (ratón, teclas o stick)
+    player.a += input.turn;
+    player.look_vertical(input.look);
 
-    // Rotación con teclado (como respaldo)
-    if window.is_key_down(KeyboardKey::KEY_LEFT) {
-        player.a -= ROTATION_SPEED;
-    }
-    if window.is_key_down(KeyboardKey::KEY_RIGHT) {
-        player.a += ROTATION_SPEED;
-    }
-    
-    // Movimiento hacia adelante
-    if window.is_key_down(KeyboardKey::KEY_UP) {
-        let new_x = player.pos.x + MOVE_SPEED * player.a.cos();
-        let new_y = player.pos.y + MOVE_SPEED * player.a.sin();
-        
-        // Verificar colisiones con margen de seguridad
-        if !check_collision_with_margin(maze, new_x, player.pos.y, BLOCK_SIZE, COLLISION_MARGIN) {
-            player.pos.x = new_x;
-        }
-        if !check_collision_with_margin(maze, player.pos.x, new_y, BLOCK_SIZE, COLLISION_MARGIN) {
-            player.pos.y = new_y;
-        }
+    // Agacharse (mantener) y saltar
+    player.crouching = input.crouch;
+    if input.jump {
+        player.jump();
     }
-    
-    // Movimiento hacia atrás
-    if window.is_key_down(KeyboardKey::KEY_DOWN) {
-        let new_x = player.pos.x - MOVE_SPEED * player.a.cos();
-        let new_y = player.pos.y - MOVE_SPEED * player.a.sin();
+    player.update_vertical(delta_time);
+
+    let move_speed = if player.crouching { MOVE_SPEED * CROUCH_SPEED_FACTOR } else { MOVE_SPEED };
+
+    // Movimiento hacia adelante o hacia atrás (con un stick, proporcional a su inclinación)
+    if input.forward != 0.0 {
+        let step = move_speed * input.forward;
+        let new_x = player.pos.x + step * player.a.cos();
+        let new_y = player.pos.y + step * player.a.sin();
         
         // Verificar colisiones con margen de seguridad
-        if !check_collision_with_margin(maze, new_x, player.pos.y, BLOCK_SIZE, COLLISION_MARGIN) {
+        if !check_collision_with_margin(maze, doors, new_x, player.pos.y, BLOCK_SIZE, COLLISION_MARGIN) {
             player.pos.x = new_x;
         }
-        if !check_collision_with_margin(maze, player.pos.x, new_y, BLOCK_SIZE, COLLISION_MARGIN) {
+        if !check_collision_with_margin(maze, doors, player.pos.x, new_y, BLOCK_SIZE, COLLISION_MARGIN) {
             player.pos.y = new_y;
         }
     }
@@ -1,16 +1,335 @@
+use std::collections::HashMap;
+use std::fmt;
 use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::io::{self, BufRead, BufReader};
 
 pub type Maze = Vec<Vec<char>>;
 
-pub fn load_maze(filename: &str) -> Vec<Vec<char>> {
-    let file = File::open(filename).unwrap();
+// Carácter de las puertas deslizantes en el archivo del laberinto
+pub const DOOR_CHAR: char = 'd';
+// Textura de los marcos (jambas) que rodean a una puerta, no aparece en el archivo
+pub const DOOR_JAMB_CHAR: char = 'j';
+// Celdas al aire libre: se recorren como espacios vacíos pero muestran el cielo en lugar del techo
+pub const OUTDOOR_CHAR: char = 'o';
+// Paredes espejo: los rayos rebotan en ellas
+pub const MIRROR_CHAR: char = 'm';
+
+// Celdas vacías: se caminan y se ven a través (espacio, triggers 't', 's', 'c' y celdas exteriores)
+// Las puertas y los portales tienen su propia lógica y no cuentan como vacías
+pub fn is_open_cell(cell: char) -> bool {
+    matches!(cell, ' ' | 't' | 's' | 'c' | OUTDOOR_CHAR)
+}
+
+// Portales: cada dígito del '1' al '9' aparece en dos celdas enlazadas entre sí
+pub fn is_portal_char(cell: char) -> bool {
+    matches!(cell, '1'..='9')
+}
+
+// Velocidad de apertura/cierre (fracción de la puerta por segundo)
+const DOOR_SPEED: f32 = 1.5;
+// A partir de esta apertura la puerta deja pasar al jugador y a los sprites
+const DOOR_PASSABLE_AMOUNT: f32 = 0.8;
+
+// Estado de una puerta deslizante. La puerta se dibuja en el centro de su celda
+// y se desliza a lo largo de su eje hasta esconderse dentro de la jamba
+pub struct Door {
+    pub col: usize,
+    pub row: usize,
+    pub horizontal: bool, // true: el plano de la puerta es horizontal (corredor norte-sur)
+    pub open_amount: f32, // 0.0 cerrada, 1.0 completamente abierta
+    pub opening: bool,
+}
+
+impl Door {
+    pub fn is_passable(&self) -> bool {
+        self.open_amount >= DOOR_PASSABLE_AMOUNT
+    }
+}
+
+// Estado de apertura de todas las puertas del laberinto, indexado por (columna, fila)
+pub struct Doors {
+    doors: HashMap<(usize, usize), Door>,
+}
+
+impl Doors {
+    pub fn new(maze: &Maze) -> Self {
+        let mut doors = HashMap::new();
+
+        // Las celdas fuera del laberinto cuentan como paredes
+        let is_solid = |col: i32, row: i32| -> bool {
+            if row < 0 || col < 0 || row as usize >= maze.len() || col as usize >= maze[row as usize].len() {
+                return true;
+            }
+            !is_open_cell(maze[row as usize][col as usize])
+        };
+
+        for (row_index, row) in maze.iter().enumerate() {
+            for (col_index, &cell) in row.iter().enumerate() {
+                if cell != DOOR_CHAR {
+                    continue;
+                }
+
+                // Si a izquierda y derecha hay paredes (u otras puertas) el corredor va de norte a sur
+                // y la puerta se coloca como un plano horizontal
+                let horizontal = is_solid(col_index as i32 - 1, row_index as i32)
+                    && is_solid(col_index as i32 + 1, row_index as i32);
+
+                doors.insert((col_index, row_index), Door {
+                    col: col_index,
+                    row: row_index,
+                    horizontal,
+                    open_amount: 0.0,
+                    opening: false,
+                });
+            }
+        }
+
+        Doors { doors }
+    }
+
+    pub fn get(&self, col: usize, row: usize) -> Option<&Door> {
+        self.doors.get(&(col, row))
+    }
+
+    // Copiar la apertura de las puertas que siguen en la misma celda (al recargar el nivel)
+    pub fn keep_state_from(&mut self, previous: &Doors) {
+        for (cell, door) in self.doors.iter_mut() {
+            if let Some(old) = previous.doors.get(cell) {
+                door.open_amount = old.open_amount;
+                door.opening = old.opening;
+            }
+        }
+    }
+
+    // Avanzar la animación de todas las puertas
+    pub fn update(&mut self, delta_time: f32) {
+        for door in self.doors.values_mut() {
+            if door.opening {
+                door.open_amount = (door.open_amount + DOOR_SPEED * delta_time).min(1.0);
+            } else {
+                door.open_amount = (door.open_amount - DOOR_SPEED * delta_time).max(0.0);
+            }
+        }
+    }
+
+    // Abrir o cerrar las puertas cercanas al jugador
+    // No se cierra una puerta si el jugador está parado dentro de su celda
+    pub fn toggle_near(&mut self, player_x: f32, player_y: f32, block_size: usize) {
+        let block_size_f = block_size as f32;
+        let player_col = (player_x / block_size_f) as usize;
+        let player_row = (player_y / block_size_f) as usize;
+
+        for door in self.doors.values_mut() {
+            let door_center_x = (door.col as f32 * block_size_f) + (block_size_f / 2.0);
+            let door_center_y = (door.row as f32 * block_size_f) + (block_size_f / 2.0);
+            let dx = player_x - door_center_x;
+            let dy = player_y - door_center_y;
+            let distance = (dx * dx + dy * dy).sqrt();
+
+            if distance > block_size_f * 1.5 {
+                continue;
+            }
+
+            if door.opening && door.col == player_col && door.row == player_row {
+                continue;
+            }
+
+            door.opening = !door.opening;
+        }
+    }
+}
+
+// Celda de portal. El portal es un nicho con una sola salida; cruzar su entrada lleva a la
+// entrada del portal pareja, girando lo necesario para salir por su corredor
+pub struct Portal {
+    pub col: usize,
+    pub row: usize,
+    pub facing: (i32, i32), // dirección desde la celda hacia su corredor
+    pub partner: (usize, usize),
+}
+
+// Pares de portales del laberinto, indexados por (columna, fila)
+// Los dígitos sin pareja o sin una celda abierta al lado se ignoran
+pub struct Portals {
+    portals: HashMap<(usize, usize), Portal>,
+}
+
+impl Portals {
+    pub fn new(maze: &Maze) -> Self {
+        let is_open = |col: i32, row: i32| -> bool {
+            if row < 0 || col < 0 {
+                return false;
+            }
+            maze.get(row as usize).and_then(|r| r.get(col as usize)).is_some_and(|&cell| is_open_cell(cell))
+        };
+
+        // Agrupar las celdas por dígito (la pareja se asigna después)
+        let mut cells_by_char: HashMap<char, Vec<Portal>> = HashMap::new();
+        for (row_index, row) in maze.iter().enumerate() {
+            for (col_index, &cell) in row.iter().enumerate() {
+                if !is_portal_char(cell) {
+                    continue;
+                }
+
+                let directions = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+                let facing = directions
+                    .into_iter()
+                    .find(|&(dx, dy)| is_open(col_index as i32 + dx, row_index as i32 + dy));
+
+                if let Some(facing) = facing {
+                    cells_by_char.entry(cell).or_default().push(Portal {
+                        col: col_index,
+                        row: row_index,
+                        facing,
+                        partner: (col_index, row_index),
+                    });
+                }
+            }
+        }
+
+        let mut portals = HashMap::new();
+        for cells in cells_by_char.into_values() {
+            let Ok([mut a, mut b]) = <[Portal; 2]>::try_from(cells) else {
+                continue;
+            };
+            a.partner = (b.col, b.row);
+            b.partner = (a.col, a.row);
+            portals.insert((a.col, a.row), a);
+            portals.insert((b.col, b.row), b);
+        }
+
+        Portals { portals }
+    }
+
+    pub fn get(&self, col: usize, row: usize) -> Option<&Portal> {
+        self.portals.get(&(col, row))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Portal> {
+        self.portals.values()
+    }
+
+    // Centro del borde abierto de un portal (el plano que hay que cruzar)
+    pub fn entrance(&self, portal: &Portal, block_size: usize) -> (f32, f32) {
+        let block_size_f = block_size as f32;
+        let center_x = (portal.col as f32 * block_size_f) + (block_size_f / 2.0);
+        let center_y = (portal.row as f32 * block_size_f) + (block_size_f / 2.0);
+        (
+            center_x + portal.facing.0 as f32 * block_size_f / 2.0,
+            center_y + portal.facing.1 as f32 * block_size_f / 2.0,
+        )
+    }
+
+    // Lleva un punto y un ángulo del portal en (col, row) al lado de su pareja
+    // Entrar por un portal equivale a salir por la pareja: la dirección de entrada se gira
+    // hasta coincidir con la salida del otro portal
+    pub fn transform(&self, col: usize, row: usize, x: f32, y: f32, angle: f32, block_size: usize) -> Option<(f32, f32, f32)> {
+        let portal = self.get(col, row)?;
+        let partner = self.get(portal.partner.0, portal.partner.1)?;
+
+        let (entry_x, entry_y) = self.entrance(portal, block_size);
+        let (exit_x, exit_y) = self.entrance(partner, block_size);
+
+        let entry_angle = (portal.facing.1 as f32).atan2(portal.facing.0 as f32);
+        let exit_angle = (partner.facing.1 as f32).atan2(partner.facing.0 as f32);
+        let rotation = exit_angle - entry_angle + std::f32::consts::PI;
+
+        let (sin_r, cos_r) = rotation.sin_cos();
+        let local_x = x - entry_x;
+        let local_y = y - entry_y;
+
+        Some((
+            exit_x + local_x * cos_r - local_y * sin_r,
+            exit_y + local_x * sin_r + local_y * cos_r,
+            angle + rotation,
+        ))
+    }
+
+    // Si (x, y) está dentro de un portal, devuelve la posición y el ángulo del otro lado
+    pub fn teleport(&self, x: f32, y: f32, angle: f32, block_size: usize) -> Option<(f32, f32, f32)> {
+        if x < 0.0 || y < 0.0 {
+            return None;
+        }
+        let col = (x / block_size as f32) as usize;
+        let row = (y / block_size as f32) as usize;
+        self.transform(col, row, x, y, angle, block_size)
+    }
+}
+
+// Palabras clave de las líneas de metadatos del nivel (ver decals.rs, level.rs y textures.rs)
+const LEVEL_DIRECTIVES: [&str; 5] = ["#decal", "#face", "#texture", "#effect", "#palette"];
+
+// Una línea de metadatos empieza con una de las palabras clave; una fila del laberinto
+// también puede empezar con '#' (una pared), así que no basta con mirar el primer carácter
+fn is_level_directive(line: &str) -> bool {
+    line.split_whitespace().next().is_some_and(|word| LEVEL_DIRECTIVES.contains(&word))
+}
+
+// Por qué no se pudo cargar un laberinto
+#[derive(Debug)]
+pub enum MazeError {
+    Io(io::Error),
+    Empty,
+    // Todas las filas deben medir lo mismo que la primera (filas numeradas desde 0)
+    RaggedRow { row: usize, width: usize, expected: usize },
+}
+
+impl fmt::Display for MazeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MazeError::Io(error) => write!(f, "{}", error),
+            MazeError::Empty => write!(f, "el laberinto no tiene filas"),
+            MazeError::RaggedRow { row, width, expected } => {
+                write!(f, "la fila {} mide {} celdas y debería medir {}", row, width, expected)
+            }
+        }
+    }
+}
+
+impl From<io::Error> for MazeError {
+    fn from(error: io::Error) -> Self {
+        MazeError::Io(error)
+    }
+}
+
+pub fn load_maze(filename: &str) -> Result<Maze, MazeError> {
+    let file = File::open(filename)?;
     let reader = BufReader::new(file);
 
-    reader
-        .lines()
-        .map(|line| line.unwrap().chars().collect())
-        .collect()
+    let mut maze: Maze = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        if !is_level_directive(&line) {
+            maze.push(line.chars().collect());
+        }
+    }
+
+    // El resto del juego asume un laberinto rectangular
+    let expected = maze.first().map_or(0, |row| row.len());
+    if expected == 0 {
+        return Err(MazeError::Empty);
+    }
+    if let Some((row, cells)) = maze.iter().enumerate().find(|(_, cells)| cells.len() != expected) {
+        return Err(MazeError::RaggedRow { row, width: cells.len(), expected });
+    }
+    Ok(maze)
+}
+
+// Celda transitable más cercana (en línea recta) a la celda (col, row), que puede estar fuera del laberinto
+pub fn nearest_passable_cell(maze: &Maze, doors: &Doors, col: i32, row: i32) -> Option<(usize, usize)> {
+    let mut nearest = None;
+    let mut nearest_distance = i32::MAX;
+    for (row_index, cells) in maze.iter().enumerate() {
+        for col_index in 0..cells.len() {
+            let dx = col_index as i32 - col;
+            let dy = row_index as i32 - row;
+            if dx * dx + dy * dy < nearest_distance && is_passable_cell(maze, doors, col_index, row_index) {
+                nearest_distance = dx * dx + dy * dy;
+                nearest = Some((col_index, row_index));
+            }
+        }
+    }
+    nearest
 }
 
 // Encontrar la posición inicial del jugador (carácter 'p')
@@ -39,7 +358,7 @@ pub fn find_player_start(maze: &Maze) -> Option<(f32, f32)> {
                        new_col >= 0 && new_col < maze[0].len() as i32 {
                         let cell_char = maze[new_row as usize][new_col as usize];
                         // Si es una celda vacía, colocar al jugador en el centro de esa celda
-                        if cell_char == ' ' {
+                        if cell_char == ' ' || cell_char == OUTDOOR_CHAR {
                             let x = (new_col as f32 * block_size) + (block_size / 2.0);
                             let y = (new_row as f32 * block_size) + (block_size / 2.0);
                             return Some((x, y));
@@ -92,30 +411,30 @@ pub fn check_goal_collision(maze: &Maze, player_x: f32, player_y: f32, block_siz
 }
 
 // Función para verificar colisiones con las paredes
-pub fn check_collision(maze: &Maze, new_x: f32, new_y: f32, block_size: usize) -> bool {
+pub fn check_collision(maze: &Maze, doors: &Doors, new_x: f32, new_y: f32, block_size: usize) -> bool {
     let grid_x = (new_x / block_size as f32) as usize;
     let grid_y = (new_y / block_size as f32) as usize;
-    
-    // Verificar límites del laberinto
-    if grid_x >= maze[0].len() || grid_y >= maze.len() {
-        return true; // Colisión con límites
-    }
-    
-    let cell = maze[grid_y][grid_x];
-    
-    // Los triggers ('t', 's', 'c') no tienen colisión, se pueden atravesar
-    // Optimización: usar match para mejor rendimiento
+    !is_passable_cell(maze, doors, grid_x, grid_y)
+}
+
+// Se puede estar dentro de la celda (col, row); fuera del laberinto nunca
+pub fn is_passable_cell(maze: &Maze, doors: &Doors, col: usize, row: usize) -> bool {
+    let Some(&cell) = maze.get(row).and_then(|cells| cells.get(col)) else {
+        return false;
+    };
+
+    // Las celdas vacías (triggers y exteriores incluidos) y los portales no tienen colisión
     match cell {
-        't' | 's' | 'c' => return false,
-        _ => {}
+        cell if is_open_cell(cell) || is_portal_char(cell) => true,
+        // Las puertas solo bloquean mientras no estén suficientemente abiertas
+        DOOR_CHAR => doors.get(col, row).is_some_and(|door| door.is_passable()),
+        // Cualquier otra celda es una pared
+        _ => false,
     }
-    
-    // Verificar si hay una pared en la nueva posición
-    cell != ' '
 }
 
 // Función para verificar colisiones con margen de seguridad
-pub fn check_collision_with_margin(maze: &Maze, x: f32, y: f32, block_size: usize, margin: f32) -> bool {
+pub fn check_collision_with_margin(maze: &Maze, doors: &Doors, x: f32, y: f32, block_size: usize, margin: f32) -> bool {
     // Verificar múltiples puntos alrededor de la entidad para evitar que se pegue a las paredes
     let points = vec![
         (x - margin, y - margin), // Esquina superior izquierda
@@ -125,7 +444,7 @@ pub fn check_collision_with_margin(maze: &Maze, x: f32, y: f32, block_size: usiz
     ];
     
     for (px, py) in points {
-        if check_collision(maze, px, py, block_size) {
+        if check_collision(maze, doors, px, py, block_size) {
             return true;
         }
     }
@@ -133,74 +452,294 @@ pub fn check_collision_with_margin(maze: &Maze, x: f32, y: f32, block_size: usiz
     false
 }
 
-// Función para verificar si hay línea de visión entre dos puntos (sin paredes)
-pub fn has_line_of_sight(maze: &Maze, from_x: f32, from_y: f32, to_x: f32, to_y: f32, block_size: usize) -> bool {
-    let dx = to_x - from_x;
-    let dy = to_y - from_y;
-    let distance = (dx * dx + dy * dy).sqrt();
-    
+// Línea de visión desde un punto hasta otro, directa o a través de un portal
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sighting {
+    pub distance: f32,                 // largo del recorrido (los portales no lo cambian)
+    pub angle: f32,                    // dirección en la que sale la línea desde el origen
+    pub portal: Option<(usize, usize)>, // portal por el que entra la línea, si no es directa
+}
+
+// Distancia entre las muestras de una línea de visión
+const SIGHT_STEP: f32 = 5.0;
+
+// Todas las líneas de visión sin paredes de `from` a `to`: la directa y las que cruzan un
+// portal. Igual que los rayos del render, la línea que entra por un portal sigue desde la
+// entrada de su pareja; por eso `to` se busca también en la posición en la que se lo ve a
+// través de cada portal (el punto llevado desde la pareja hacia este lado)
+pub fn sight_lines(maze: &Maze, doors: &Doors, portals: &Portals, from: (f32, f32), to: (f32, f32), block_size: usize) -> Vec<Sighting> {
+    let apparent_targets = portals
+        .iter()
+        .filter_map(|portal| portals.transform(portal.col, portal.row, to.0, to.1, 0.0, block_size))
+        .map(|(x, y, _)| (x, y));
+
+    std::iter::once(to)
+        .chain(apparent_targets)
+        .filter_map(|target| follow_sight_line(maze, doors, portals, from, target, to, block_size))
+        .collect()
+}
+
+// Recorre la línea de `from` a `target` cruzando los portales que encuentre; solo vale si
+// termina justo en `to` (si cruza un portal que no correspondía llega a otro lugar)
+fn follow_sight_line(maze: &Maze, doors: &Doors, portals: &Portals, from: (f32, f32), target: (f32, f32), to: (f32, f32), block_size: usize) -> Option<Sighting> {
+    let distance = (target.0 - from.0).hypot(target.1 - from.1);
+    let sighting = Sighting {
+        distance,
+        angle: (target.1 - from.1).atan2(target.0 - from.0),
+        portal: None,
+    };
     // Si está muy cerca, asumir que hay línea de visión
     if distance < 10.0 {
-        return true;
+        return (target == to).then_some(sighting);
     }
-    
-    // Calcular dirección normalizada (no se usa directamente, pero se calcula para consistencia)
-    let _dir_x = dx / distance;
-    let _dir_y = dy / distance;
-    
-    // Verificar puntos a lo largo de la línea usando step_size pequeño para precisión
-    let step_size = 5.0;
-    let num_steps = (distance / step_size) as usize + 1;
-    
-    for step in 0..num_steps {
-        let t = (step as f32 * step_size) / distance;
-        let t = t.min(1.0); // Asegurar que no exceda 1.0
-        
-        // Calcular posición actual en la línea
-        let current_x = from_x + dx * t;
-        let current_y = from_y + dy * t;
-        
-        // Verificar si hay una pared en este punto
-        if check_collision(maze, current_x, current_y, block_size) {
-            return false; // Hay una pared bloqueando la visión
+
+    let mut sighting = sighting;
+    let (mut x, mut y) = from;
+    let mut target = target;
+    'legs: loop {
+        let dx = target.0 - x;
+        let dy = target.1 - y;
+        let leg_length = dx.hypot(dy);
+        let num_steps = (leg_length / SIGHT_STEP) as usize + 1;
+
+        for step in 1..=num_steps {
+            let t = (step as f32 * SIGHT_STEP / leg_length).min(1.0);
+            let current_x = x + dx * t;
+            let current_y = y + dy * t;
+            if current_x < 0.0 || current_y < 0.0 {
+                return None;
+            }
+            let col = (current_x / block_size as f32) as usize;
+            let row = (current_y / block_size as f32) as usize;
+
+            // Al entrar en un portal, la línea y su destino siguen del lado de la pareja
+            if portals.get(col, row).is_some() {
+                if sighting.portal.is_some() {
+                    return None;
+                }
+                sighting.portal = Some((col, row));
+                let (exit_x, exit_y, _) = portals.transform(col, row, current_x, current_y, 0.0, block_size)?;
+                let (target_x, target_y, _) = portals.transform(col, row, target.0, target.1, 0.0, block_size)?;
+                (x, y) = (exit_x, exit_y);
+                target = (target_x, target_y);
+                continue 'legs;
+            }
+
+            // Hay una pared bloqueando la visión
+            if check_collision(maze, doors, current_x, current_y, block_size) {
+                return None;
+            }
         }
+        break;
     }
-    
-    true // No hay paredes bloqueando la visión
+
+    ((target.0 - to.0).hypot(target.1 - to.1) < 1.0).then_some(sighting)
 }
 
 // Función para encontrar triggers activados (cuando el jugador pasa por ellos)
 // Retorna un vector de (posición x, posición y, carácter del trigger) de los triggers que el jugador está activando
 pub fn find_activated_triggers(maze: &Maze, player_x: f32, player_y: f32, block_size: usize) -> Vec<(f32, f32, char)> {
+    // Usar 70% del tamaño del bloque como radio de detección
+    let detection_radius = block_size as f32 * 0.7;
+
+    // Si el jugador está dentro del radio, el trigger está activado
+    find_triggers(maze, block_size)
+        .into_iter()
+        .filter(|&(trigger_x, trigger_y, _)| (player_x - trigger_x).hypot(player_y - trigger_y) <= detection_radius)
+        .collect()
+}
+
+// Todos los triggers del laberinto: (centro x, centro y, carácter del trigger)
+pub fn find_triggers(maze: &Maze, block_size: usize) -> Vec<(f32, f32, char)> {
     let block_size_f = block_size as f32;
-    let mut activated = Vec::new();
-    
+    let mut triggers = Vec::new();
+
     // Lista de caracteres que son triggers
-    let trigger_chars = vec!['t', 's', 'c'];
-    
-    // Buscar todas las posiciones donde hay un trigger en el laberinto
+    let trigger_chars = ['t', 's', 'c'];
+
     for (row_index, row) in maze.iter().enumerate() {
         for (col_index, &cell) in row.iter().enumerate() {
             if trigger_chars.contains(&cell) {
                 // Calcular el centro del bloque trigger
                 let trigger_center_x = (col_index as f32 * block_size_f) + (block_size_f / 2.0);
                 let trigger_center_y = (row_index as f32 * block_size_f) + (block_size_f / 2.0);
-                
-                // Calcular la distancia del jugador al centro del trigger
-                let dx = player_x - trigger_center_x;
-                let dy = player_y - trigger_center_y;
-                let distance = (dx * dx + dy * dy).sqrt();
-                
-                // Usar 70% del tamaño del bloque como radio de detección
-                let detection_radius = block_size_f * 0.7;
-                
-                // Si el jugador está dentro del radio, el trigger está activado
-                if distance <= detection_radius {
-                    activated.push((trigger_center_x, trigger_center_y, cell));
-                }
+                triggers.push((trigger_center_x, trigger_center_y, cell));
             }
         }
     }
-    
-    activated
-}
\ No newline at end of file
+
+    triggers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::PI;
+
+    fn maze_from(rows: &[&str]) -> Maze {
+        rows.iter().map(|row| row.chars().collect()).collect()
+    }
+
+    // Corredor norte-sur con una puerta en (1, 1)
+    fn door_maze() -> Maze {
+        maze_from(&["+ +", "+d+", "+ +"])
+    }
+
+    #[test]
+    fn door_orientation_follows_the_corridor() {
+        let doors = Doors::new(&door_maze());
+        assert!(doors.get(1, 1).unwrap().horizontal);
+
+        let doors = Doors::new(&maze_from(&["+++", " d ", "+++"]));
+        assert!(!doors.get(1, 1).unwrap().horizontal);
+    }
+
+    #[test]
+    fn door_opens_and_closes_over_time() {
+        let mut doors = Doors::new(&door_maze());
+        doors.toggle_near(150.0, 50.0, 100);
+        assert!(doors.get(1, 1).unwrap().opening);
+
+        doors.update(0.5);
+        let door = doors.get(1, 1).unwrap();
+        assert!((door.open_amount - 0.75).abs() < 1e-6);
+        assert!(!door.is_passable());
+
+        doors.update(1.0);
+        assert_eq!(doors.get(1, 1).unwrap().open_amount, 1.0);
+
+        doors.toggle_near(150.0, 50.0, 100);
+        doors.update(10.0);
+        assert_eq!(doors.get(1, 1).unwrap().open_amount, 0.0);
+    }
+
+    #[test]
+    fn door_does_not_close_on_the_player() {
+        let mut doors = Doors::new(&door_maze());
+        doors.toggle_near(150.0, 150.0, 100);
+        doors.toggle_near(150.0, 150.0, 100);
+        assert!(doors.get(1, 1).unwrap().opening);
+    }
+
+    #[test]
+    fn door_blocks_until_open_enough() {
+        let maze = door_maze();
+        let mut doors = Doors::new(&maze);
+        assert!(check_collision(&maze, &doors, 150.0, 150.0, 100));
+
+        doors.toggle_near(150.0, 50.0, 100);
+        doors.update(DOOR_PASSABLE_AMOUNT / DOOR_SPEED + 0.01);
+        assert!(!check_collision(&maze, &doors, 150.0, 150.0, 100));
+        assert!(check_collision(&maze, &doors, 50.0, 150.0, 100));
+    }
+
+    // Escribe un archivo de nivel temporal y lo carga con load_maze
+    fn load_from_text(name: &str, text: &str) -> Result<Maze, MazeError> {
+        let path = std::env::temp_dir().join(format!("maze_runner_test_{}_{}.txt", std::process::id(), name));
+        std::fs::write(&path, text).unwrap();
+        let maze = load_maze(path.to_str().unwrap());
+        std::fs::remove_file(&path).unwrap();
+        maze
+    }
+
+    #[test]
+    fn load_maze_keeps_rows_starting_with_a_wall_char() {
+        let maze = load_from_text("hash_rows", "#####\n#   #\n#####\n#decal 1 1 N A\n#effect fog on").unwrap();
+        assert_eq!(maze, maze_from(&["#####", "#   #", "#####"]));
+    }
+
+    #[test]
+    fn load_maze_rejects_empty_and_ragged_mazes() {
+        assert!(matches!(load_from_text("empty", ""), Err(MazeError::Empty)));
+        assert!(matches!(load_from_text("only_header", "#palette gameboy"), Err(MazeError::Empty)));
+        assert!(matches!(
+            load_from_text("ragged", "+--+\n|  |\n+-+"),
+            Err(MazeError::RaggedRow { row: 2, width: 3, expected: 4 })
+        ));
+        assert!(matches!(load_maze("no/existe.txt"), Err(MazeError::Io(_))));
+    }
+
+    #[test]
+    fn nearest_passable_cell_skips_walls_and_closed_doors() {
+        let maze = maze_from(&["+++++", "+ d +", "+++++"]);
+        let mut doors = Doors::new(&maze);
+        assert_eq!(nearest_passable_cell(&maze, &doors, 2, 0), Some((1, 1)));
+
+        doors.toggle_near(150.0, 150.0, 100);
+        doors.update(1.0);
+        assert_eq!(nearest_passable_cell(&maze, &doors, 2, 0), Some((2, 1)));
+        assert_eq!(nearest_passable_cell(&maze_from(&["+++"]), &doors, 0, 0), None);
+    }
+
+    #[test]
+    fn reloaded_doors_keep_their_state() {
+        let maze = door_maze();
+        let mut doors = Doors::new(&maze);
+        doors.toggle_near(150.0, 50.0, 100);
+        doors.update(1.0);
+
+        let mut reloaded = Doors::new(&maze);
+        reloaded.keep_state_from(&doors);
+        assert!(reloaded.get(1, 1).unwrap().is_passable());
+    }
+
+    // Dos corredores sin conexión: el portal '1' en (1, 1) mira al este y su pareja en (5, 3) al oeste
+    fn portal_corridors() -> Maze {
+        maze_from(&["#######", "#1   ##", "#######", "##   1#", "#######"])
+    }
+
+    #[test]
+    fn portal_transform_rotates_into_the_partner_corridor() {
+        // El portal en (1, 1) mira al este y su pareja en (3, 3) al norte
+        let maze = maze_from(&["#####", "#1  #", "### #", "###1#", "#####"]);
+        let portals = Portals::new(&maze);
+        assert_eq!(portals.get(1, 1).unwrap().partner, (3, 3));
+        assert_eq!(portals.get(3, 3).unwrap().facing, (0, -1));
+
+        // Entrar hacia el oeste por el primero es salir hacia el norte por el segundo
+        let (x, y, angle) = portals.transform(1, 1, 150.0, 150.0, PI, 100).unwrap();
+        assert!((x - 350.0).abs() < 1e-3 && (y - 250.0).abs() < 1e-3);
+        assert!((angle.sin() + 1.0).abs() < 1e-5 && angle.cos().abs() < 1e-5);
+    }
+
+    #[test]
+    fn teleport_only_moves_points_inside_a_portal() {
+        let portals = Portals::new(&portal_corridors());
+        let (x, y, angle) = portals.teleport(150.0, 150.0, PI, 100).unwrap();
+        assert!((x - 450.0).abs() < 1e-3 && (y - 350.0).abs() < 1e-3);
+        assert!((angle.cos() + 1.0).abs() < 1e-5);
+
+        assert!(portals.teleport(350.0, 150.0, PI, 100).is_none());
+        assert!(portals.teleport(-10.0, 150.0, PI, 100).is_none());
+    }
+
+    #[test]
+    fn sight_line_goes_through_a_portal_pair() {
+        let maze = portal_corridors();
+        let portals = Portals::new(&maze);
+        let doors = Doors::new(&maze);
+
+        // La pared entre los corredores tapa la línea directa; por el portal se ve al oeste
+        let lines = sight_lines(&maze, &doors, &portals, (350.0, 150.0), (250.0, 350.0), 100);
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0].portal, Some((1, 1)));
+        assert!((lines[0].distance - 400.0).abs() < 1e-3);
+        assert!((lines[0].angle.cos() + 1.0).abs() < 1e-5);
+
+        // Dentro del mismo corredor la línea es directa
+        let lines = sight_lines(&maze, &doors, &portals, (350.0, 150.0), (450.0, 150.0), 100);
+        assert_eq!(lines, vec![Sighting { distance: 100.0, angle: 0.0, portal: None }]);
+    }
+
+    #[test]
+    fn sight_line_is_blocked_by_walls_on_both_sides_of_the_portal() {
+        let maze = maze_from(&["#######", "#1 # ##", "#######", "## # 1#", "#######"]);
+        let portals = Portals::new(&maze);
+        let doors = Doors::new(&maze);
+
+        // Pared antes del portal
+        assert!(sight_lines(&maze, &doors, &portals, (450.0, 150.0), (450.0, 350.0), 100).is_empty());
+        // Pared después del portal
+        assert!(sight_lines(&maze, &doors, &portals, (250.0, 150.0), (250.0, 350.0), 100).is_empty());
+    }
+}
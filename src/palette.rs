@@ -0,0 +1,303 @@
+// palette.rs - Modos de salida retro: cuantizar el frame final a una paleta fija
+//
+// Se eligen por nivel con una línea en el archivo del laberinto:
+//   #palette <cga|gameboy|ruta.pal> [none|bayer|diffusion]
+// o en el juego con la tecla F5. Los archivos .pal pueden ser JASC-PAL ("r g b" por línea)
+// o una lista de colores hexadecimales ("#rrggbb"); las demás líneas se ignoran.
+
+use raylib::color::Color;
+use std::fs;
+
+use crate::framebuffer::{pack_color, unpack_color, Framebuffer};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Dither {
+    None,
+    Bayer,     // tramado ordenado con una matriz de Bayer de 4x4
+    Diffusion, // difusión de error de Floyd-Steinberg
+}
+
+impl Dither {
+    fn parse(text: &str) -> Option<Dither> {
+        match text {
+            "none" => Some(Dither::None),
+            "bayer" => Some(Dither::Bayer),
+            "diffusion" => Some(Dither::Diffusion),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Palette {
+    pub name: String,
+    pub colors: Vec<Color>,
+}
+
+impl Palette {
+    // CGA, modo 4 colores de alta intensidad (negro, cian, magenta, blanco)
+    pub fn cga() -> Self {
+        Palette {
+            name: "CGA".to_string(),
+            colors: vec![
+                Color::new(0, 0, 0, 255),
+                Color::new(85, 255, 255, 255),
+                Color::new(255, 85, 255, 255),
+                Color::new(255, 255, 255, 255),
+            ],
+        }
+    }
+
+    // Los cuatro verdes de la Game Boy original
+    pub fn gameboy() -> Self {
+        Palette {
+            name: "Game Boy".to_string(),
+            colors: vec![
+                Color::new(15, 56, 15, 255),
+                Color::new(48, 98, 48, 255),
+                Color::new(139, 172, 15, 255),
+                Color::new(155, 188, 15, 255),
+            ],
+        }
+    }
+
+    // Paleta incorporada por nombre, o un archivo .pal
+    pub fn by_name(name: &str) -> Option<Self> {
+        match name {
+            "cga" => Some(Palette::cga()),
+            "gameboy" => Some(Palette::gameboy()),
+            path => Palette::load(path),
+        }
+    }
+
+    // Leer un archivo .pal; devuelve None si no se puede leer o no tiene colores
+    pub fn load(path: &str) -> Option<Self> {
+        let text = fs::read_to_string(path).ok()?;
+        let colors: Vec<Color> = text.lines().filter_map(parse_palette_line).collect();
+        if colors.is_empty() {
+            return None;
+        }
+        Some(Palette {
+            name: path.to_string(),
+            colors,
+        })
+    }
+
+    // Color de la paleta más cercano (distancia RGB ponderada según la sensibilidad del ojo)
+    fn nearest(&self, r: f32, g: f32, b: f32) -> Color {
+        let distance = |color: &Color| {
+            let dr = color.r as f32 - r;
+            let dg = color.g as f32 - g;
+            let db = color.b as f32 - b;
+            2.0 * dr * dr + 4.0 * dg * dg + 3.0 * db * db
+        };
+        *self
+            .colors
+            .iter()
+            .min_by(|a, b| distance(a).total_cmp(&distance(b)))
+            .expect("la paleta no puede estar vacía")
+    }
+}
+
+// "r g b" en decimal o "#rrggbb" / "rrggbb" en hexadecimal
+fn parse_palette_line(line: &str) -> Option<Color> {
+    let parts: Vec<&str> = line.split_whitespace().collect();
+    if let [r, g, b] = parts.as_slice() {
+        return Some(Color::new(r.parse().ok()?, g.parse().ok()?, b.parse().ok()?, 255));
+    }
+    let hex = line.trim().trim_start_matches('#');
+    if hex.len() != 6 {
+        return None;
+    }
+    let value = u32::from_str_radix(hex, 16).ok()?;
+    Some(Color::new((value >> 16) as u8, (value >> 8) as u8, value as u8, 255))
+}
+
+// Modo de salida retro: paleta y tramado
+#[derive(Debug, Clone)]
+pub struct RetroMode {
+    pub palette: Palette,
+    pub dither: Dither,
+}
+
+impl RetroMode {
+    // Leer la línea '#palette' de un archivo de nivel (si tiene una válida)
+    pub fn from_level_file(filename: &str) -> Option<Self> {
+        let text = fs::read_to_string(filename).ok()?;
+        text.lines().find_map(|line| {
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            let (name, dither) = match parts.as_slice() {
+                ["#palette", name] => (*name, Dither::Bayer),
+                ["#palette", name, dither] => (*name, Dither::parse(dither)?),
+                _ => return None,
+            };
+            Some(RetroMode {
+                palette: Palette::by_name(name)?,
+                dither,
+            })
+        })
+    }
+
+    // Modos que se recorren con la tecla: None (colores normales) y luego cada paleta y tramado
+    pub fn next(current: Option<&RetroMode>) -> Option<RetroMode> {
+        let modes = [
+            (Palette::cga(), Dither::Bayer),
+            (Palette::cga(), Dither::Diffusion),
+            (Palette::gameboy(), Dither::Bayer),
+            (Palette::gameboy(), Dither::Diffusion),
+        ];
+        let index = match current {
+            None => 0,
+            Some(mode) => {
+                let position = modes
+                    .iter()
+                    .position(|(palette, dither)| palette.name == mode.palette.name && *dither == mode.dither);
+                match position {
+                    Some(position) => position + 1,
+                    None => 0,
+                }
+            }
+        };
+        modes
+            .into_iter()
+            .nth(index)
+            .map(|(palette, dither)| RetroMode { palette, dither })
+    }
+
+    pub fn name(&self) -> String {
+        let dither = match self.dither {
+            Dither::None => "sin tramado",
+            Dither::Bayer => "Bayer",
+            Dither::Diffusion => "difusión",
+        };
+        format!("{} ({})", self.palette.name, dither)
+    }
+}
+
+// Matriz de Bayer de 4x4 (umbrales 0..15)
+const BAYER_4X4: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+// Cuantizar el framebuffer completo a la paleta del modo
+pub fn apply_retro_mode(framebuffer: &mut Framebuffer, mode: &RetroMode) {
+    let width = framebuffer.width as usize;
+    let palette = &mode.palette;
+    if palette.colors.is_empty() || width == 0 {
+        return;
+    }
+
+    match mode.dither {
+        Dither::None => {
+            for pixel in framebuffer.pixels_mut() {
+                let color = unpack_color(*pixel);
+                *pixel = pack_color(palette.nearest(color.r as f32, color.g as f32, color.b as f32));
+            }
+        }
+        Dither::Bayer => {
+            // Desplazar cada píxel según su umbral antes de elegir el color; con pocas
+            // paletas de colores el desplazamiento tiene que ser mayor
+            let spread = (255.0 / palette.colors.len() as f32).max(32.0);
+            for (index, pixel) in framebuffer.pixels_mut().iter_mut().enumerate() {
+                let (x, y) = (index % width, index / width);
+                let offset = (BAYER_4X4[y % 4][x % 4] as f32 + 0.5) / 16.0 - 0.5;
+                let color = unpack_color(*pixel);
+                let shift = offset * spread;
+                let nearest = palette.nearest(color.r as f32 + shift, color.g as f32 + shift, color.b as f32 + shift);
+                *pixel = pack_color(nearest);
+            }
+        }
+        Dither::Diffusion => {
+            // Error acumulado de la fila actual y de la siguiente (r, g, b por píxel)
+            let mut current = vec![[0.0f32; 3]; width + 2];
+            let mut next = vec![[0.0f32; 3]; width + 2];
+            for row in framebuffer.pixels_mut().chunks_exact_mut(width) {
+                for (x, pixel) in row.iter_mut().enumerate() {
+                    let color = unpack_color(*pixel);
+                    let error = current[x + 1];
+                    let wanted = [
+                        color.r as f32 + error[0],
+                        color.g as f32 + error[1],
+                        color.b as f32 + error[2],
+                    ];
+                    let chosen = palette.nearest(wanted[0], wanted[1], wanted[2]);
+                    *pixel = pack_color(chosen);
+
+                    // Repartir el error: 7/16 a la derecha, 3/16, 5/16 y 1/16 en la fila de abajo
+                    let chosen = [chosen.r as f32, chosen.g as f32, chosen.b as f32];
+                    for channel in 0..3 {
+                        let diff = wanted[channel] - chosen[channel];
+                        current[x + 2][channel] += diff * 7.0 / 16.0;
+                        next[x][channel] += diff * 3.0 / 16.0;
+                        next[x + 1][channel] += diff * 5.0 / 16.0;
+                        next[x + 2][channel] += diff / 16.0;
+                    }
+                }
+                std::mem::swap(&mut current, &mut next);
+                next.iter_mut().for_each(|error| *error = [0.0; 3]);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Archivo temporal con `text` (cada prueba usa su propio nombre)
+    fn temp_file(name: &str, text: &str) -> String {
+        let path = std::env::temp_dir().join(format!("mazerunner_{}_{}", std::process::id(), name));
+        fs::write(&path, text).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn palette_lines_accept_decimal_and_hex_colors() {
+        assert_eq!(parse_palette_line("12 8 16"), Some(Color::new(12, 8, 16, 255)));
+        assert_eq!(parse_palette_line("#ff8000"), Some(Color::new(255, 128, 0, 255)));
+        assert_eq!(parse_palette_line("  0a0b0c "), Some(Color::new(10, 11, 12, 255)));
+
+        // Cabecera JASC-PAL y líneas inválidas
+        for line in ["JASC-PAL", "0100", "16", "", "300 0 0", "#ff80", "#gg0000"] {
+            assert_eq!(parse_palette_line(line), None, "{:?}", line);
+        }
+    }
+
+    #[test]
+    fn jasc_palette_file_loads_its_colors() {
+        let path = temp_file("jasc.pal", "JASC-PAL\n0100\n2\n12 8 16\n#302024\n");
+        let palette = Palette::load(&path).unwrap();
+        assert_eq!(palette.colors, vec![Color::new(12, 8, 16, 255), Color::new(48, 32, 36, 255)]);
+
+        let empty = temp_file("empty.pal", "JASC-PAL\n0100\n0\n");
+        assert!(Palette::load(&empty).is_none());
+        assert!(Palette::load("no/existe.pal").is_none());
+    }
+
+    #[test]
+    fn level_palette_line_picks_palette_and_dither() {
+        let path = temp_file("level.txt", "+--+\n|  |\n+--+\n#palette gameboy diffusion\n");
+        let mode = RetroMode::from_level_file(&path).unwrap();
+        assert_eq!(mode.palette.name, "Game Boy");
+        assert_eq!(mode.dither, Dither::Diffusion);
+
+        let path = temp_file("level_bad.txt", "+--+\n#palette cga wobble\n");
+        assert!(RetroMode::from_level_file(&path).is_none());
+    }
+
+    #[test]
+    fn next_cycles_through_the_builtin_modes_and_back_to_none() {
+        let mut names = Vec::new();
+        let mut mode = RetroMode::next(None);
+        while let Some(current) = mode {
+            names.push(current.name());
+            mode = RetroMode::next(Some(&current));
+        }
+        assert_eq!(names, ["CGA (Bayer)", "CGA (difusión)", "Game Boy (Bayer)", "Game Boy (difusión)"]);
+
+        // Una paleta del nivel que no está en la lista vuelve al principio
+        let custom = RetroMode {
+            palette: Palette { name: "assets/spooky.pal".to_string(), colors: vec![Color::BLACK] },
+            dither: Dither::Bayer,
+        };
+        assert_eq!(RetroMode::next(Some(&custom)).unwrap().name(), "CGA (Bayer)");
+    }
+}
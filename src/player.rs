@@ -1,8 +1,81 @@
 use raylib::prelude::*;
 use std::f32::consts::PI;
 
+// Altura de los ojos sobre el piso (las paredes miden 100)
+const STAND_EYE_HEIGHT: f32 = 50.0;
+const CROUCH_EYE_HEIGHT: f32 = 25.0;
+const CROUCH_SPEED: f32 = 6.0; // Velocidad de la transición al agacharse (por segundo)
+
+// Salto
+const JUMP_SPEED: f32 = 220.0;
+const GRAVITY: f32 = 700.0;
+
+// Límite de la inclinación vertical (fracción de la altura de pantalla que se desplaza el horizonte)
+const MAX_PITCH: f32 = 0.5;
+
 pub struct Player {
     pub pos: Vector2,
     pub a: f32,
     pub fov: f32,
+    pub pitch: f32,          // desplazamiento del horizonte; positivo mira hacia arriba
+    pub jump_height: f32,    // altura sobre el piso durante un salto
+    pub vertical_speed: f32,
+    pub crouching: bool,
+    pub crouch_amount: f32,  // 0.0 de pie, 1.0 agachado
+}
+
+impl Player {
+    pub fn new(x: f32, y: f32, a: f32, fov: f32) -> Self {
+        Player {
+            pos: Vector2::new(x, y),
+            a,
+            fov,
+            pitch: 0.0,
+            jump_height: 0.0,
+            vertical_speed: 0.0,
+            crouching: false,
+            crouch_amount: 0.0,
+        }
+    }
+
+    // Altura actual de los ojos: de pie, agachado o en el aire
+    pub fn eye_height(&self) -> f32 {
+        STAND_EYE_HEIGHT - (STAND_EYE_HEIGHT - CROUCH_EYE_HEIGHT) * self.crouch_amount + self.jump_height
+    }
+
+    // Fila de la pantalla donde queda el horizonte (la vista se inclina desplazándolo)
+    pub fn horizon(&self, screen_height: f32) -> f32 {
+        screen_height / 2.0 + self.pitch * screen_height
+    }
+
+    pub fn look_vertical(&mut self, delta: f32) {
+        self.pitch = (self.pitch + delta).clamp(-MAX_PITCH, MAX_PITCH);
+    }
+
+    pub fn is_on_ground(&self) -> bool {
+        self.jump_height <= 0.0
+    }
+
+    // No se puede saltar agachado ni en el aire
+    pub fn jump(&mut self) {
+        if self.is_on_ground() && !self.crouching {
+            self.vertical_speed = JUMP_SPEED;
+        }
+    }
+
+    // Avanzar el salto y la transición de agacharse
+    pub fn update_vertical(&mut self, delta_time: f32) {
+        if self.vertical_speed != 0.0 || !self.is_on_ground() {
+            self.vertical_speed -= GRAVITY * delta_time;
+            self.jump_height += self.vertical_speed * delta_time;
+            if self.jump_height <= 0.0 {
+                self.jump_height = 0.0;
+                self.vertical_speed = 0.0;
+            }
+        }
+
+        let target = if self.crouching { 1.0 } else { 0.0 };
+        let step = CROUCH_SPEED * delta_time;
+        self.crouch_amount += (target - self.crouch_amount).clamp(-step, step);
+    }
 }
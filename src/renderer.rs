@@ -0,0 +1,245 @@
+// renderer.rs - Render por software de la vista 3D y el minimapa
+// No usa la ventana de raylib: todo se dibuja en un Framebuffer en memoria
+
+use raylib::prelude::*;
+
+use crate::caster::{render3d, SceneView};
+use crate::framebuffer::{Framebuffer, UpscaleFilter};
+use crate::level::Level;
+use crate::line::{fill_sector, line};
+use crate::maze::{is_open_cell, is_portal_char, Maze};
+use crate::player::Player;
+use crate::settings::RenderSettings;
+use crate::sprites::{draw_reflected_sprites, draw_sprite, Sprite};
+use crate::textures::TextureManager;
+use crate::visual_effects::{EffectContext, PostPipeline, VisualEffects};
+
+// Región rectangular de la ventana donde se dibuja la vista de un jugador
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Viewport {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Viewport {
+    pub fn full(width: u32, height: u32) -> Self {
+        Viewport { x: 0, y: 0, width, height }
+    }
+
+    // Dividir la ventana en `count` vistas lado a lado (pantalla dividida vertical)
+    pub fn split(width: u32, height: u32, count: u32) -> Vec<Viewport> {
+        let count = count.max(1);
+        (0..count)
+            .map(|i| {
+                let x = width * i / count;
+                Viewport { x, y: 0, width: width * (i + 1) / count - x, height }
+            })
+            .collect()
+    }
+
+    // Framebuffer donde se dibuja la escena de esta vista: la resolución interna del preset,
+    // reducida en la misma proporción que la vista ocupa de la ventana
+    pub fn scene_framebuffer(&self, render_settings: &RenderSettings, window_width: u32, window_height: u32) -> Framebuffer {
+        let width = (render_settings.render_width * self.width / window_width).max(1);
+        let height = (render_settings.render_height * self.height / window_height).max(1);
+        let mut framebuffer = Framebuffer::new(width, height, Color::BLACK);
+        framebuffer.set_background_color(Color::new(50, 50, 100, 255));
+        framebuffer
+    }
+
+    // Campo de visión para esta vista: en una vista más angosta que la ventana se abre
+    // menos, para que las paredes no se vean comprimidas de lado
+    pub fn field_of_view(&self, fov: f32, window_width: u32) -> f32 {
+        fov * self.width as f32 / window_width as f32
+    }
+
+    // Copiar la escena ya dibujada a esta región de la ventana
+    pub fn present(&self, framebuffer: &mut Framebuffer, scene: &Framebuffer, filter: UpscaleFilter) {
+        framebuffer.blit_scaled(scene, self.x as i32, self.y as i32, self.width, self.height, filter);
+    }
+}
+
+pub fn render_world(framebuffer: &mut Framebuffer, player: &Player) {
+    framebuffer.set_current_color(Color::GRAY);
+
+    // El techo y el piso se separan en el horizonte, que se desplaza al inclinar la vista
+    let horizon = (player.horizon(framebuffer.height as f32).max(0.0) as u32).min(framebuffer.height);
+
+    // Draw sky usando rectángulo (mucho más eficiente)
+    framebuffer.draw_rectangle(0, 0, framebuffer.width, horizon);
+
+    // Draw ground usando rectángulo
+    framebuffer.draw_rectangle(0, horizon, framebuffer.width, framebuffer.height - horizon);
+}
+
+pub const MINIMAP_SIZE: u32 = 280;
+
+pub fn render_minimap(framebuffer: &mut Framebuffer, viewport: &Viewport, maze: &Maze, player: &Player, sprites: &[Sprite], block_size: usize) {
+    // Tamaño del minimapa
+    let minimap_size = MINIMAP_SIZE;
+    let minimap_scale = 3u32; // Escala: cada celda del laberinto será de 3x3 píxeles en el minimapa
+    
+    // Posición en la esquina superior derecha de la vista
+    let minimap_x = (viewport.x + viewport.width).saturating_sub(minimap_size + 10);
+    let minimap_y = viewport.y + 10;
+    
+    // Calcular el offset para centrar el minimapa en la posición del jugador
+    let player_grid_x = (player.pos.x / block_size as f32) as i32;
+    let player_grid_y = (player.pos.y / block_size as f32) as i32;
+    
+    // Calcular el rango visible del laberinto
+    let visible_cells = (minimap_size / minimap_scale) as i32;
+    let start_grid_x = (player_grid_x - visible_cells / 2).max(0);
+    let start_grid_y = (player_grid_y - visible_cells / 2).max(0);
+    let end_grid_x = (start_grid_x + visible_cells).min(maze[0].len() as i32);
+    let end_grid_y = (start_grid_y + visible_cells).min(maze.len() as i32);
+    
+    // Dibujar el laberinto en el minimapa
+    for (row_index, row) in maze.iter().enumerate() {
+        let grid_y = row_index as i32;
+        if grid_y < start_grid_y || grid_y >= end_grid_y {
+            continue;
+        }
+        
+        for (col_index, &cell) in row.iter().enumerate() {
+            let grid_x = col_index as i32;
+            if grid_x < start_grid_x || grid_x >= end_grid_x {
+                continue;
+            }
+            
+            // Los triggers ('t', 's', 'c') son transparentes y no se dibujan en el minimapa
+            if !is_open_cell(cell) {
+                // Dibujar muro en el minimapa
+                let minimap_cell_x = minimap_x + ((grid_x - start_grid_x) * minimap_scale as i32) as u32;
+                let minimap_cell_y = minimap_y + ((grid_y - start_grid_y) * minimap_scale as i32) as u32;
+                
+                framebuffer.set_current_color(if is_portal_char(cell) { Color::PURPLE } else { Color::RED });
+                for py in 0..minimap_scale {
+                    for px in 0..minimap_scale {
+                        if minimap_cell_x + px < framebuffer.width && minimap_cell_y + py < framebuffer.height {
+                            framebuffer.set_pixel(minimap_cell_x + px, minimap_cell_y + py);
+                        }
+                    }
+                }
+            }
+        }
+    }
+    
+    // Dibujar el jugador en el minimapa
+    let player_minimap_x = minimap_x + ((player_grid_x - start_grid_x) * minimap_scale as i32) as u32;
+    let player_minimap_y = minimap_y + ((player_grid_y - start_grid_y) * minimap_scale as i32) as u32;
+    
+    framebuffer.set_current_color(Color::GREEN);
+    // Dibujar un pequeño círculo o cuadrado para el jugador
+    for dy in -1..=1 {
+        for dx in -1..=1 {
+            let px = (player_minimap_x as i32 + dx).max(0) as u32;
+            let py = (player_minimap_y as i32 + dy).max(0) as u32;
+            if px < framebuffer.width && py < framebuffer.height {
+                framebuffer.set_pixel(px, py);
+            }
+        }
+    }
+    
+    // Dibujar sprites y su FOV en el minimapa
+    for sprite in sprites {
+        let sprite_grid_x = (sprite.pos.x / block_size as f32) as i32;
+        let sprite_grid_y = (sprite.pos.y / block_size as f32) as i32;
+        
+        // Solo dibujar si el sprite está en el área visible del minimapa
+        if sprite_grid_x >= start_grid_x && sprite_grid_x < end_grid_x &&
+           sprite_grid_y >= start_grid_y && sprite_grid_y < end_grid_y {
+            
+            let sprite_minimap_x = minimap_x + ((sprite_grid_x - start_grid_x) * minimap_scale as i32) as u32;
+            let sprite_minimap_y = minimap_y + ((sprite_grid_y - start_grid_y) * minimap_scale as i32) as u32;
+            
+            // Dibujar FOV del sprite (cono de visión)
+            if sprite.player_detected {
+                framebuffer.set_current_color(Color::new(255, 100, 100, 150)); // Rojo claro cuando detecta
+            } else {
+                framebuffer.set_current_color(Color::new(100, 100, 255, 100)); // Azul claro cuando no detecta
+            }
+            
+            // Cono de visión relleno (translúcido)
+            let fov_half = sprite.fov / 2.0;
+            let fov_range = 15.0; // Rango del FOV en el minimapa
+            let sprite_center = Vector2::new(sprite_minimap_x as f32, sprite_minimap_y as f32);
+            fill_sector(framebuffer, sprite_center, fov_range, sprite.facing_angle - fov_half, sprite.facing_angle + fov_half);
+            
+            // Dibujar el sprite en el minimapa
+            if sprite.player_detected {
+                framebuffer.set_current_color(Color::RED); // Rojo cuando detecta al jugador
+            } else {
+                framebuffer.set_current_color(Color::BLUE); // Azul cuando no detecta
+            }
+            
+            // Dibujar un pequeño cuadrado para el sprite
+            for dy in -1..=1 {
+                for dx in -1..=1 {
+                    let px = (sprite_minimap_x as i32 + dx).max(0) as u32;
+                    let py = (sprite_minimap_y as i32 + dy).max(0) as u32;
+                    if px < framebuffer.width && py < framebuffer.height {
+                        framebuffer.set_pixel(px, py);
+                    }
+                }
+            }
+            
+            // Dibujar dirección del sprite (línea pequeña)
+            framebuffer.set_current_color(Color::YELLOW);
+            let dir_length = 4.0;
+            let dir_end_x = sprite_minimap_x as f32 + sprite.facing_angle.cos() * dir_length;
+            let dir_end_y = sprite_minimap_y as f32 + sprite.facing_angle.sin() * dir_length;
+            
+            line(framebuffer, sprite_center, Vector2::new(dir_end_x, dir_end_y));
+        }
+    }
+}
+
+// Dibuja la vista 3D completa en `framebuffer` (a la resolución interna del preset):
+// techo y piso, paredes, sprites, reflejos y los efectos habilitados por el preset
+// Los sprites deben venir ordenados por distancia (ver `update_sprite_distances`)
+#[allow(clippy::too_many_arguments)]
+pub fn render_scene(
+    framebuffer: &mut Framebuffer,
+    player: &Player,
+    level: &Level,
+    sprites: &[Sprite],
+    texture_manager: &TextureManager,
+    render_settings: &RenderSettings,
+    post_pipeline: &PostPipeline,
+    visual_effects: &VisualEffects,
+    time: f32,
+) {
+    framebuffer.clear();
+    render_world(framebuffer, player);
+    let view = SceneView {
+        player,
+        texture_manager,
+        maze: &level.maze,
+        doors: &level.doors,
+        portals: &level.portals,
+        decals: &level.decals,
+        light_map: &level.light_map,
+        ray_scale: render_settings.ray_scale,
+        texture_sampling: render_settings.texture_sampling,
+    };
+    let (mut z_buffer, reflections) = render3d(framebuffer, &view);
+
+    // Los reflejos van primero: están detrás del cristal, así que un sprite real delante
+    // del espejo debe taparlos y no al revés
+    draw_reflected_sprites(framebuffer, player, sprites, texture_manager, &level.light_map, &reflections);
+    for sprite in sprites {
+        draw_sprite(framebuffer, player, sprite, texture_manager, &level.light_map, &mut z_buffer);
+    }
+
+    // Post-procesado: los efectos activos, en orden
+    let ctx = EffectContext {
+        player,
+        effects: visual_effects,
+        z_buffer: &z_buffer,
+        time,
+    };
+    post_pipeline.apply(framebuffer, &ctx);
+}
@@ -1,7 +1,8 @@
 // screens.rs - Sistema de pantallas reutilizable
 
 use raylib::prelude::*;
-use crate::framebuffer::Framebuffer;
+use crate::font::{BitmapFont, TextAlign, TextStyle};
+use crate::framebuffer::{Framebuffer, UpscaleFilter};
 
 pub enum ScreenType {
     MainMenu,
@@ -15,12 +16,13 @@ pub enum MenuState {
 
 pub struct ScreenManager {
     background_texture: Option<Texture2D>,
-    background_image: Option<Image>,
+    background_image: Option<Framebuffer>,
     victory_texture: Option<Texture2D>,
-    victory_image: Option<Image>,
+    victory_image: Option<Framebuffer>,
     current_level: usize,
     menu_state: MenuState,
     selected_option: usize, // 0: Comenzar, 1: Selector de niveles, 2: Salir
+    font: BitmapFont,
 }
 
 impl ScreenManager {
@@ -37,6 +39,10 @@ impl ScreenManager {
             window.load_texture_from_image(raylib_thread, img).ok()
         });
 
+        // Copias en CPU para dibujarlas en el framebuffer
+        let background_image = background_image.as_ref().map(Framebuffer::from_image);
+        let victory_image = victory_image.as_ref().map(Framebuffer::from_image);
+
         ScreenManager {
             background_texture,
             background_image,
@@ -45,6 +51,7 @@ impl ScreenManager {
             current_level: 1,
             menu_state: MenuState::MainMenu,
             selected_option: 0,
+            font: BitmapFont::builtin(),
         }
     }
 
@@ -73,6 +80,10 @@ impl ScreenManager {
         self.menu_state = state;
     }
 
+    pub fn font(&self) -> &BitmapFont {
+        &self.font
+    }
+
     pub fn get_selected_option(&self) -> usize {
         self.selected_option
     }
@@ -93,121 +104,36 @@ pub fn render_screen(
     let screen_height = framebuffer.height;
 
     // Dibujar fondo
-    if let Some(ref bg_texture) = screen_manager.background_texture {
-        // Dibujar imagen de fondo escalada
-        let bg_width = bg_texture.width as f32;
-        let bg_height = bg_texture.height as f32;
-        let scale_x = screen_width as f32 / bg_width;
-        let scale_y = screen_height as f32 / bg_height;
-        let scale = scale_x.min(scale_y);
-
-        let scaled_width = bg_width * scale;
-        let scaled_height = bg_height * scale;
-        let x = (screen_width as f32 - scaled_width) / 2.0;
-        let y = (screen_height as f32 - scaled_height) / 2.0;
-
-        // Dibujar en el framebuffer usando la imagen
-        if let Some(ref bg_image) = screen_manager.background_image {
-            for py in 0..(scaled_height as u32).min(screen_height) {
-                for px in 0..(scaled_width as u32).min(screen_width) {
-                    let src_x = ((px as f32 / scale) as u32).min(bg_width as u32 - 1);
-                    let src_y = ((py as f32 / scale) as u32).min(bg_height as u32 - 1);
-                    
-                    unsafe {
-                        let raylib_color = raylib::ffi::GetImageColor(
-                            raylib::ffi::Image {
-                                data: bg_image.data,
-                                width: bg_image.width,
-                                height: bg_image.height,
-                                mipmaps: bg_image.mipmaps,
-                                format: bg_image.format,
-                            },
-                            src_x as i32,
-                            src_y as i32
-                        );
-                        
-                        let alpha = match bg_image.format {
-                            4 => raylib_color.a,
-                            3 => 255,
-                            _ => 255,
-                        };
-                        
-                        let color = Color::new(raylib_color.r, raylib_color.g, raylib_color.b, alpha);
-                        framebuffer.set_pixel_color((x as u32 + px).min(screen_width - 1), (y as u32 + py).min(screen_height - 1), color);
-                    }
-                }
-            }
-        }
-    } else {
-        // Si no hay imagen, usar fondo negro
-        framebuffer.set_background_color(Color::BLACK);
-        framebuffer.clear();
-    }
+    draw_fitted_background(framebuffer, screen_manager.background_image.as_ref());
 
         match screen_type {
         ScreenType::MainMenu => {
             render_main_menu(framebuffer, screen_width, screen_height, screen_manager);
         }
         ScreenType::Victory => {
-            render_victory_screen(framebuffer, screen_width, screen_height, screen_manager);
+            render_victory_screen(framebuffer, screen_manager);
         }
     }
 }
 
-fn render_victory_background(
-    framebuffer: &mut Framebuffer,
-    screen_width: u32,
-    screen_height: u32,
-    screen_manager: &ScreenManager,
-) {
-    // Dibujar imagen de fondo de victoria
-    if let Some(ref victory_image) = screen_manager.victory_image {
-        let bg_width = victory_image.width as f32;
-        let bg_height = victory_image.height as f32;
-        let scale_x = screen_width as f32 / bg_width;
-        let scale_y = screen_height as f32 / bg_height;
-        let scale = scale_x.min(scale_y);
-
-        let scaled_width = bg_width * scale;
-        let scaled_height = bg_height * scale;
-        let x = (screen_width as f32 - scaled_width) / 2.0;
-        let y = (screen_height as f32 - scaled_height) / 2.0;
-
-        // Dibujar en el framebuffer usando la imagen
-        for py in 0..(scaled_height as u32).min(screen_height) {
-            for px in 0..(scaled_width as u32).min(screen_width) {
-                let src_x = ((px as f32 / scale) as u32).min(bg_width as u32 - 1);
-                let src_y = ((py as f32 / scale) as u32).min(bg_height as u32 - 1);
-                
-                unsafe {
-                    let raylib_color = raylib::ffi::GetImageColor(
-                        raylib::ffi::Image {
-                            data: victory_image.data,
-                            width: victory_image.width,
-                            height: victory_image.height,
-                            mipmaps: victory_image.mipmaps,
-                            format: victory_image.format,
-                        },
-                        src_x as i32,
-                        src_y as i32
-                    );
-                    
-                    let alpha = match victory_image.format {
-                        4 => raylib_color.a,
-                        3 => 255,
-                        _ => 255,
-                    };
-                    
-                    let color = Color::new(raylib_color.r, raylib_color.g, raylib_color.b, alpha);
-                    framebuffer.set_pixel_color((x as u32 + px).min(screen_width - 1), (y as u32 + py).min(screen_height - 1), color);
-                }
-            }
-        }
-    } else {
-        // Si no hay imagen, usar fondo negro
+// Dibujar una imagen escalada para caber en la pantalla, centrada y sin deformarla
+// Sin imagen, la pantalla queda en negro
+fn draw_fitted_background(framebuffer: &mut Framebuffer, image: Option<&Framebuffer>) {
+    let Some(image) = image else {
         framebuffer.set_background_color(Color::BLACK);
         framebuffer.clear();
-    }
+        return;
+    };
+
+    let scale_x = framebuffer.width as f32 / image.width as f32;
+    let scale_y = framebuffer.height as f32 / image.height as f32;
+    let scale = scale_x.min(scale_y);
+
+    let scaled_width = (image.width as f32 * scale) as u32;
+    let scaled_height = (image.height as f32 * scale) as u32;
+    let x = (framebuffer.width - scaled_width) as i32 / 2;
+    let y = (framebuffer.height - scaled_height) as i32 / 2;
+    framebuffer.blit_scaled(image, x, y, scaled_width, scaled_height, UpscaleFilter::Nearest);
 }
 
 fn render_main_menu(
@@ -216,71 +142,70 @@ fn render_main_menu(
     height: u32,
     screen_manager: &ScreenManager,
 ) {
-    let center_x = width / 2;
-    let center_y = height / 2;
-
-    // Dibujar título (simulado con rectángulos, ya que no tenemos renderizado de texto en framebuffer)
-    // Por ahora, usaremos un método simple de dibujar texto básico
-    framebuffer.set_current_color(Color::WHITE);
-    // Nota: Para texto real, necesitaríamos usar raylib directamente en swap_buffer
-    // Por ahora, dibujamos un indicador visual
+    let center_x = width as i32 / 2;
+    let center_y = height as i32 / 2;
+    let font = &screen_manager.font;
+
+    // Opciones del menú actual, la seleccionada y las instrucciones
+    let (options, selected, instructions_x, instructions) = match screen_manager.get_menu_state() {
+        MenuState::MainMenu => (
+            vec!["Comenzar", "Selector de Niveles", "Salir"],
+            screen_manager.get_selected_option(),
+            center_x - 200,
+            "Flechas: Navegar | Enter: Seleccionar",
+        ),
+        MenuState::LevelSelect => (
+            vec!["Nivel 1", "Nivel 2", "Nivel 3"],
+            screen_manager.get_current_level() - 1,
+            center_x - 250,
+            "Flechas: Navegar | Enter: Seleccionar | ESC: Volver",
+        ),
+    };
 
-    // Opciones de nivel
-    let level_options = vec!["Nivel 1", "Nivel 2", "Nivel 3"];
     let option_spacing = 80;
     let start_y = center_y - 50;
+    let option_style = TextStyle::new(4, Color::WHITE).outlined(Color::BLACK);
 
-    for (i, _level_text) in level_options.iter().enumerate() {
-        let y = start_y + (i as u32 * option_spacing);
-        let is_selected = (i + 1) == screen_manager.get_current_level();
-
-        // Dibujar indicador de selección
-        if is_selected {
-            framebuffer.set_current_color(Color::YELLOW);
-            // Dibujar un rectángulo alrededor de la opción seleccionada
-            for px in (center_x - 100)..(center_x + 100) {
-                for py in (y - 5)..(y + 25) {
-                    if px < width && py < height {
-                        framebuffer.set_pixel(px, py);
-                    }
-                }
-            }
-        } else {
-            framebuffer.set_current_color(Color::GRAY);
-        }
+    for (i, option_text) in options.iter().enumerate() {
+        let y = start_y + i as i32 * option_spacing;
 
-        // Dibujar indicador de opción (círculo pequeño)
-        for dx in -3..=3 {
-            for dy in -3..=3 {
-                let px = (center_x as i32 - 120 + dx).max(0) as u32;
-                let py = (y as i32 + 10 + dy).max(0) as u32;
-                if px < width && py < height && (dx * dx + dy * dy) <= 9 {
-                    framebuffer.set_pixel(px, py);
-                }
-            }
+        // Dibujar flecha si está seleccionado
+        if i == selected {
+            framebuffer.draw_text(font, ">", center_x - 200, y, &option_style);
         }
-    }
 
-    // Instrucciones (dibujadas visualmente)
-    framebuffer.set_current_color(Color::WHITE);
-    // Indicador de instrucciones en la parte inferior
-    for px in (center_x - 200)..(center_x + 200) {
-        for py in (height - 30)..(height - 20) {
-            if px < width && py < height {
-                framebuffer.set_pixel(px, py);
-            }
-        }
+        // Dibujar texto de la opción
+        framebuffer.draw_text(font, option_text, center_x - 150, y, &option_style);
     }
+
+    // Instrucciones
+    let instructions_style = TextStyle::new(2, Color::GRAY).outlined(Color::BLACK);
+    framebuffer.draw_text(font, instructions, instructions_x, height as i32 - 100, &instructions_style);
 }
 
-fn render_victory_screen(
-    framebuffer: &mut Framebuffer,
-    width: u32,
-    height: u32,
-    screen_manager: &ScreenManager,
-) {
-    // Dibujar fondo de victoria (pic0.png)
-    render_victory_background(framebuffer, width, height, screen_manager);
+fn render_victory_screen(framebuffer: &mut Framebuffer, screen_manager: &ScreenManager) {
+    // Dibujar fondo de victoria (door.png)
+    draw_fitted_background(framebuffer, screen_manager.victory_image.as_ref());
+
+    let center_x = framebuffer.width as i32 / 2;
+    let center_y = framebuffer.height as i32 / 2;
+    let current_level = screen_manager.get_current_level();
+    let font = &screen_manager.font;
+
+    // Mensaje de victoria: "Room" + número de nivel
+    let room_text = format!("Room {}", current_level);
+    framebuffer.draw_text(font, &room_text, center_x, center_y - 100, &TextStyle::new(8, Color::GREEN).aligned(TextAlign::Center).outlined(Color::BLACK));
+
+    let (message, color) = if current_level < 3 {
+        ("Enter: Siguiente Nivel", Color::YELLOW)
+    } else {
+        ("¡Todos los niveles completados!", Color::GOLD)
+    };
+    let message_style = TextStyle::new(3, color).aligned(TextAlign::Center).outlined(Color::BLACK);
+    framebuffer.draw_text(font, message, center_x, center_y + 50, &message_style);
+
+    let menu_style = TextStyle::new(3, Color::WHITE).aligned(TextAlign::Center).outlined(Color::BLACK);
+    framebuffer.draw_text(font, "ESC: Menú Principal", center_x, center_y + 120, &menu_style);
 }
 
 pub enum MenuAction {
@@ -0,0 +1,87 @@
+// settings.rs - Resolución interna de render y presets de calidad
+
+use crate::framebuffer::UpscaleFilter;
+use crate::textures::TextureSampling;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum QualityPreset {
+    Low,
+    Medium,
+    High,
+}
+
+impl QualityPreset {
+    pub fn name(&self) -> &str {
+        match self {
+            QualityPreset::Low => "Baja",
+            QualityPreset::Medium => "Media",
+            QualityPreset::High => "Alta",
+        }
+    }
+
+    // Siguiente preset (para alternar con una tecla)
+    pub fn next(&self) -> QualityPreset {
+        match self {
+            QualityPreset::Low => QualityPreset::Medium,
+            QualityPreset::Medium => QualityPreset::High,
+            QualityPreset::High => QualityPreset::Low,
+        }
+    }
+}
+
+// Configuración de render de la vista 3D
+// La escena se dibuja a `render_width` x `render_height` y luego se escala al tamaño de la ventana
+#[derive(Debug, Clone)]
+pub struct RenderSettings {
+    pub preset: QualityPreset,
+    pub render_width: u32,
+    pub render_height: u32,
+    pub ray_scale: usize,          // Columnas cubiertas por cada rayo
+    pub upscale_filter: UpscaleFilter,
+    pub texture_sampling: TextureSampling, // Muestreo de las paredes (siempre con mipmaps)
+    pub flashlight_pass: bool,
+    pub anxiety_pass: bool,
+    pub damage_pass: bool,
+}
+
+impl RenderSettings {
+    pub fn from_preset(preset: QualityPreset, window_width: u32, window_height: u32) -> Self {
+        match preset {
+            // Mitad de resolución y un rayo cada 2 columnas: pensado para equipos lentos
+            QualityPreset::Low => RenderSettings {
+                preset,
+                render_width: window_width / 2,
+                render_height: window_height / 2,
+                ray_scale: 2,
+                upscale_filter: UpscaleFilter::Nearest,
+                texture_sampling: TextureSampling::Nearest,
+                flashlight_pass: true,
+                anxiety_pass: false,
+                damage_pass: false,
+            },
+            QualityPreset::Medium => RenderSettings {
+                preset,
+                render_width: window_width * 3 / 4,
+                render_height: window_height * 3 / 4,
+                ray_scale: 1,
+                upscale_filter: UpscaleFilter::Bilinear,
+                texture_sampling: TextureSampling::Bilinear,
+                flashlight_pass: true,
+                anxiety_pass: true,
+                damage_pass: true,
+            },
+            // Resolución completa, sin escalado
+            QualityPreset::High => RenderSettings {
+                preset,
+                render_width: window_width,
+                render_height: window_height,
+                ray_scale: 1,
+                upscale_filter: UpscaleFilter::Nearest,
+                texture_sampling: TextureSampling::Bilinear,
+                flashlight_pass: true,
+                anxiety_pass: true,
+                damage_pass: true,
+            },
+        }
+    }
+}
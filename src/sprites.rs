@@ -1,12 +1,38 @@
 // sprites.rs
 
 use raylib::prelude::*;
+use crate::caster::{tint_reflection, Reflection};
 use crate::framebuffer::Framebuffer;
 use crate::player::Player;
-use crate::textures::TextureManager;
-use crate::maze::{Maze, check_collision_with_margin, has_line_of_sight};
+use crate::textures::{TextureManager, TextureRef};
+use crate::lights::{apply_light, LightMap};
+use crate::maze::{Maze, Doors, Portals, Sighting, check_collision_with_margin, sight_lines};
 use std::f32::consts::PI;
 
+// Vistas de las hojas de sprites: una cada 45 grados alrededor del sprite
+pub const SPRITE_VIEWS: usize = 8;
+
+// Estado de la animación de un sprite, según lo que está haciendo su IA
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SpriteState {
+    Idle,   // quieto, sin ver al jugador
+    Walk,   // persiguiendo al jugador
+    Attack, // junto al jugador
+    Scare,  // al aparecer en un trigger
+}
+
+impl SpriteState {
+    pub fn parse(text: &str) -> Option<SpriteState> {
+        match text {
+            "idle" => Some(SpriteState::Idle),
+            "walk" => Some(SpriteState::Walk),
+            "attack" => Some(SpriteState::Attack),
+            "scare" => Some(SpriteState::Scare),
+            _ => None,
+        }
+    }
+}
+
 pub struct Sprite {
     pub pos: Vector2,
     pub texture_char: char,
@@ -15,6 +41,8 @@ pub struct Sprite {
     pub facing_angle: f32,  // Ángulo hacia donde está mirando el sprite
     pub fov: f32,            // Campo de visión del sprite (en radianes)
     pub player_detected: bool, // Si el sprite ha detectado al jugador
+    pub state: SpriteState,  // Animación actual
+    pub state_time: f32,     // Segundos desde que entró en `state`
 }
 
 impl Sprite {
@@ -27,42 +55,53 @@ impl Sprite {
             facing_angle: 0.0, // Empezar mirando hacia la derecha
             fov: std::f32::consts::PI * 2.0 / 3.0, // 120 grados de FOV (más amplio que el jugador)
             player_detected: false,
+            state: SpriteState::Scare, // Los sprites aparecen asustando
+            state_time: 0.0,
         }
     }
-    
-    // Verificar si el jugador está dentro del FOV del sprite
-    // Requiere el maze y block_size para verificar línea de visión
-    pub fn can_see_player(&self, player_pos: Vector2, maze: &Maze, block_size: usize) -> bool {
-        let dx = player_pos.x - self.pos.x;
-        let dy = player_pos.y - self.pos.y;
-        let distance = (dx * dx + dy * dy).sqrt();
-        
-        // Si está muy lejos, no puede ver
-        if distance > 800.0 {
-            return false;
-        }
-        
-        // Calcular ángulo desde el sprite al jugador
-        let angle_to_player = dy.atan2(dx);
-        
-        // Calcular diferencia angular entre la dirección del sprite y el jugador
-        let mut angle_diff = angle_to_player - self.facing_angle;
-        
-        // Normalizar a [-PI, PI]
-        while angle_diff > PI {
-            angle_diff -= 2.0 * PI;
-        }
-        while angle_diff < -PI {
-            angle_diff += 2.0 * PI;
-        }
-        
-        // Verificar si el jugador está dentro del FOV
-        if angle_diff.abs() > self.fov / 2.0 {
-            return false;
+
+    // Cambiar de animación (la nueva empieza desde su primer cuadro)
+    pub fn set_state(&mut self, state: SpriteState) {
+        if self.state != state {
+            self.state = state;
+            self.state_time = 0.0;
         }
-        
-        // Verificar línea de visión (que no haya paredes entre el sprite y el jugador)
-        has_line_of_sight(maze, self.pos.x, self.pos.y, player_pos.x, player_pos.y, block_size)
+    }
+
+    // Vista de la hoja que corresponde a una cámara en `camera`: 0 si el sprite la mira de frente,
+    // 2 si la cámara está a su derecha, 4 si lo ve de espaldas y 6 si está a su izquierda.
+    // En el mapa la y crece hacia abajo, así que los ángulos crecen en sentido horario: sumar 90°
+    // a `facing_angle` apunta a la derecha del sprite (mirando al este, su derecha es el sur)
+    pub fn view_from(&self, camera: Vector2) -> usize {
+        let angle_to_camera = (camera.y - self.pos.y).atan2(camera.x - self.pos.x);
+        let relative = (angle_to_camera - self.facing_angle).rem_euclid(2.0 * PI);
+        let step = 2.0 * PI / SPRITE_VIEWS as f32;
+        (relative / step).round() as usize % SPRITE_VIEWS
+    }
+
+    // Cuadro que se ve desde `camera` según el estado y la vista
+    pub fn frame_from<'a>(&self, camera: Vector2, texture_manager: &'a TextureManager) -> Option<TextureRef<'a>> {
+        texture_manager.sprite_frame(self.texture_char, self.state, self.view_from(camera), self.state_time)
+    }
+    
+    // Verificar si el jugador está dentro del FOV del sprite y sin paredes en medio, mirando
+    // también a través de los portales. Devuelve la línea de visión más corta
+    pub fn can_see_player(&self, player_pos: Vector2, detection_range: f32, maze: &Maze, doors: &Doors, portals: &Portals, block_size: usize) -> Option<Sighting> {
+        sight_lines(maze, doors, portals, (self.pos.x, self.pos.y), (player_pos.x, player_pos.y), block_size)
+            .into_iter()
+            .filter(|sighting| sighting.distance <= detection_range)
+            .filter(|sighting| {
+                // Diferencia angular entre la dirección del sprite y la línea, en [-PI, PI]
+                let mut angle_diff = sighting.angle - self.facing_angle;
+                while angle_diff > PI {
+                    angle_diff -= 2.0 * PI;
+                }
+                while angle_diff < -PI {
+                    angle_diff += 2.0 * PI;
+                }
+                angle_diff.abs() <= self.fov / 2.0
+            })
+            .min_by(|a, b| a.distance.total_cmp(&b.distance))
     }
 }
 
@@ -72,16 +111,34 @@ const ALPHA_THRESHOLD: u8 = 128;
 // Color que se considera transparente (magenta)
 const TRANSPARENT_COLOR: Color = Color::new(255, 0, 255, 255);
 
-pub fn draw_sprite(
+// Altura del mundo a la que se centran los sprites
+const SPRITE_CENTER_HEIGHT: f32 = 50.0;
+
+// Alcance de la vista de los enemigos (se reduce si el jugador va agachado)
+const DETECTION_RANGE: f32 = 800.0;
+const CROUCH_DETECTION_RANGE: f32 = 400.0;
+
+// Margen de seguridad de los sprites contra las paredes
+pub const SPRITE_COLLISION_MARGIN: f32 = 12.0;
+
+// Silueta del jugador que aparece en los espejos
+pub const PLAYER_SILHOUETTE_CHAR: char = 'y';
+const SILHOUETTE_COLOR: Color = Color::new(12, 10, 16, 255);
+
+// Proyecta un sprite ubicado en `pos` sobre la pantalla
+// `visible(x, distance)` decide si la columna x muestra el sprite (z-buffer) y `shade(color, distance)`
+// sombrea cada píxel opaco de la textura
+fn project_sprite(
     framebuffer: &mut Framebuffer,
     player: &Player,
-    sprite: &Sprite,
-    texture_manager: &TextureManager,
-    z_buffer: &mut Vec<f32>,
+    pos: Vector2,
+    texture: TextureRef,
+    visible: impl Fn(usize, f32) -> bool,
+    shade: impl Fn(Color, f32) -> Color,
 ) {
     // Calcular ángulo desde el jugador al sprite
-    let dx = sprite.pos.x - player.pos.x;
-    let dy = sprite.pos.y - player.pos.y;
+    let dx = pos.x - player.pos.x;
+    let dy = pos.y - player.pos.y;
     let sprite_angle = dy.atan2(dx);
 
     // Normalizar diferencia angular a [-PI, PI]
@@ -112,73 +169,175 @@ pub fn draw_sprite(
     // Calcular posición horizontal en pantalla (centrada)
     let screen_x = (framebuffer.width as f32 / 2.0 + angle_diff / (player.fov / 2.0) * framebuffer.width as f32 / 2.0) as usize;
     
+    // Centro vertical del sprite: sigue al horizonte y a la altura de los ojos del jugador
+    // (los sprites se centran a media altura de pared)
+    let pixels_per_unit = framebuffer.height as f32 / 2.0 / distance;
+    let center_y = player.horizon(framebuffer.height as f32) + (player.eye_height() - SPRITE_CENTER_HEIGHT) * pixels_per_unit;
+
     // Calcular esquina superior izquierda del sprite en pantalla
+    // La parte de arriba puede quedar fuera de la pantalla; se recorta sin deformar la textura
     let start_x = screen_x.saturating_sub(sprite_size / 2);
-    let start_y = (framebuffer.height as f32 / 2.0 - sprite_size as f32 / 2.0) as usize;
+    let top_y = center_y - sprite_size as f32 / 2.0;
+    let start_y = (top_y.max(0.0) as usize).min(framebuffer.height as usize);
     
     let end_x = (start_x + sprite_size).min(framebuffer.width as usize);
-    let end_y = (start_y + sprite_size).min(framebuffer.height as usize);
+    let end_y = ((top_y + sprite_size as f32).max(0.0) as usize).min(framebuffer.height as usize);
 
-    // Obtener dimensiones de la textura
-    let (tex_width, tex_height) = texture_manager.get_texture_dimensions(sprite.texture_char)
-        .unwrap_or((128, 128));
+    // Dimensiones de la textura
+    let (tex_width, tex_height) = (texture.width(), texture.height());
 
     for x in start_x..end_x {
+        // Verificar z-buffer - solo dibujar si el sprite está más cerca que la pared
+        if !visible(x, distance) {
+            continue;
+        }
+
         for y in start_y..end_y {
-            // Verificar z-buffer - solo dibujar si el sprite está más cerca que la pared
-            if x < z_buffer.len() && distance < z_buffer[x] {
-                // Mapear píxel de pantalla a coordenadas de textura
-                let tx = ((x - start_x) * tex_width as usize / sprite_size) as u32;
-                let ty = ((y - start_y) * tex_height as usize / sprite_size) as u32;
-
-                let color = texture_manager.get_pixel_color(sprite.texture_char, tx, ty);
-
-                 // Función para verificar si un color es transparente
-                 let is_transparent = |c: Color| -> bool {
-                     // Verificar canal alpha si está disponible
-                     if c.a < ALPHA_THRESHOLD {
-                         return true;
-                     }
-                    
-                     // Solo considerar transparentes:
-                     // 1. Negro puro (0,0,0)
-                     // 2. Magenta puro (255,0,255) - color de transparencia estándar
-                     // 3. Píxeles con alpha muy bajo
-                     // 4. Solo negros extremos (brillo < 2)
-                     (c.r == 0 && c.g == 0 && c.b == 0) || 
-                     (c.r == 255 && c.g == 0 && c.b == 255) // Solo negro extremo
-                 };
-
-                // Saltar píxeles transparentes
-                if !is_transparent(color) {
-                    // Aplicar intensidad basada en la distancia (más suave y controlada)
-                    // Reducir aún más el efecto de oscurecimiento para preservar negros
-                    let intensity = (1.0 - (distance / 1000.0).min(0.4)).max(0.6);
-                    
-                    // Para colores muy oscuros, aplicar menos oscurecimiento
-                    let brightness = (color.r as u32 + color.g as u32 + color.b as u32) / 3;
-                    let final_intensity = if brightness < 5 {
-                        // Para colores oscuros, usar intensidad más alta
-                        intensity.max(0.8)
-                    } else {
-                        intensity
-                    };
-                    
-                    // Aplicar la intensidad
-                    let r = (color.r as f32 * final_intensity) as u8;
-                    let g = (color.g as f32 * final_intensity) as u8;
-                    let b = (color.b as f32 * final_intensity) as u8;
-                    let final_color = Color::new(r, g, b, 255);
-                    
-                    framebuffer.set_current_color(final_color);
-                    framebuffer.set_pixel(x as u32, y as u32);
-                }
+            // Mapear píxel de pantalla a coordenadas de textura
+            let tx = ((x - start_x) * tex_width as usize / sprite_size) as u32;
+            let ty = ((y as f32 - top_y) * tex_height as f32 / sprite_size as f32) as u32;
+
+            let color = texture.texel(tx, ty);
+
+             // Función para verificar si un color es transparente
+             let is_transparent = |c: Color| -> bool {
+                 // Verificar canal alpha si está disponible
+                 if c.a < ALPHA_THRESHOLD {
+                     return true;
+                 }
+                
+                 // Solo considerar transparentes:
+                 // 1. Negro puro (0,0,0)
+                 // 2. Magenta puro (255,0,255) - color de transparencia estándar
+                 // 3. Píxeles con alpha muy bajo
+                 // 4. Solo negros extremos (brillo < 2)
+                 (c.r == 0 && c.g == 0 && c.b == 0) || 
+                 (c.r == 255 && c.g == 0 && c.b == 255) // Solo negro extremo
+             };
+
+            // Saltar píxeles transparentes
+            if !is_transparent(color) {
+                framebuffer.set_current_color(shade(color, distance));
+                framebuffer.set_pixel(x as u32, y as u32);
+            }
+        }
+    }
+}
+
+// Sombreado normal de un sprite según su distancia y la luz que le llega
+fn shade_sprite(color: Color, distance: f32, light: [f32; 3]) -> Color {
+    // Aplicar intensidad basada en la distancia (más suave y controlada)
+    // Reducir aún más el efecto de oscurecimiento para preservar negros
+    let intensity = (1.0 - (distance / 1000.0).min(0.4)).max(0.6);
+    
+    // Para colores muy oscuros, aplicar menos oscurecimiento
+    let brightness = (color.r as u32 + color.g as u32 + color.b as u32) / 3;
+    let final_intensity = if brightness < 5 {
+        // Para colores oscuros, usar intensidad más alta
+        intensity.max(0.8)
+    } else {
+        intensity
+    };
+    
+    // Aplicar la intensidad
+    let r = (color.r as f32 * final_intensity) as u8;
+    let g = (color.g as f32 * final_intensity) as u8;
+    let b = (color.b as f32 * final_intensity) as u8;
+    apply_light(Color::new(r, g, b, 255), light)
+}
+
+pub fn draw_sprite(
+    framebuffer: &mut Framebuffer,
+    player: &Player,
+    sprite: &Sprite,
+    texture_manager: &TextureManager,
+    light_map: &LightMap,
+    z_buffer: &mut [f32],
+) {
+    // Cuadro según el estado del sprite y el ángulo desde el que lo ve el jugador
+    let Some(texture) = sprite.frame_from(player.pos, texture_manager) else {
+        return;
+    };
+
+    // Luz de las luces puntuales en la posición del sprite
+    let light = light_map.sample(sprite.pos.x, sprite.pos.y);
+
+    project_sprite(
+        framebuffer,
+        player,
+        sprite.pos,
+        texture,
+        |x, distance| x < z_buffer.len() && distance < z_buffer[x],
+        |color, distance| shade_sprite(color, distance, light),
+    );
+}
+
+// Dibuja los sprites y la silueta del jugador reflejados en los espejos visibles
+// Cada reflejo se proyecta desde la posición simétrica respecto al espejo y solo aparece en las
+// columnas que ven ese espejo, detrás del cristal y delante de la pared reflejada
+pub fn draw_reflected_sprites(
+    framebuffer: &mut Framebuffer,
+    player: &Player,
+    sprites: &[Sprite],
+    texture_manager: &TextureManager,
+    light_map: &LightMap,
+    reflections: &[Option<Reflection>],
+) {
+    // Espejos distintos vistos en este frame
+    let mut mirrors: Vec<Reflection> = Vec::new();
+    for reflection in reflections.iter().flatten() {
+        if !mirrors.iter().any(|mirror| mirror.same_mirror(reflection)) {
+            mirrors.push(*reflection);
+        }
+    }
+
+    // (x virtual, y virtual, textura, luz, es la silueta del jugador, espejo)
+    let mut images = Vec::new();
+    for mirror in &mirrors {
+        // El espejo muestra cada sprite como se ve desde el reflejo del jugador
+        let (camera_x, camera_y) = mirror.mirror_point(player.pos.x, player.pos.y);
+        for sprite in sprites {
+            if mirror.same_side(sprite.pos.x, sprite.pos.y, player.pos.x, player.pos.y) {
+                let Some(texture) = sprite.frame_from(Vector2::new(camera_x, camera_y), texture_manager) else {
+                    continue;
+                };
+                let (x, y) = mirror.mirror_point(sprite.pos.x, sprite.pos.y);
+                let light = light_map.sample(sprite.pos.x, sprite.pos.y);
+                images.push((x, y, texture, light, false, *mirror));
             }
         }
+
+        if let Some(texture) = texture_manager.texture(PLAYER_SILHOUETTE_CHAR) {
+            images.push((camera_x, camera_y, texture, [0.0; 3], true, *mirror));
+        }
+    }
+
+    // Más lejanos primero, igual que los sprites normales
+    let distance_to = |x: f32, y: f32| (x - player.pos.x).hypot(y - player.pos.y);
+    images.sort_by(|a, b| distance_to(b.0, b.1).partial_cmp(&distance_to(a.0, a.1)).unwrap());
+
+    for (x, y, texture, light, silhouette, mirror) in images {
+        project_sprite(
+            framebuffer,
+            player,
+            Vector2::new(x, y),
+            texture,
+            |column, distance| {
+                reflections.get(column).copied().flatten().is_some_and(|reflection| {
+                    reflection.same_mirror(&mirror)
+                        && distance > reflection.mirror_distance
+                        && distance < reflection.depth
+                })
+            },
+            |color, distance| {
+                let color = if silhouette { SILHOUETTE_COLOR } else { shade_sprite(color, distance, light) };
+                tint_reflection(color, 1)
+            },
+        );
     }
 }
 
-pub fn update_sprite_distances(sprites: &mut Vec<Sprite>, player: &Player) {
+pub fn update_sprite_distances(sprites: &mut [Sprite], player: &Player) {
     for sprite in sprites.iter_mut() {
         let dx = sprite.pos.x - player.pos.x;
         let dy = sprite.pos.y - player.pos.y;
@@ -192,24 +351,38 @@ pub fn update_sprite_distances(sprites: &mut Vec<Sprite>, player: &Player) {
 
 // Función de IA para que el sprite persiga al jugador
 // Utiliza el sistema de colisiones para evitar que el sprite atraviese paredes
-// El sprite solo persigue cuando detecta al jugador dentro de su FOV
-pub fn update_sprite_ai(sprites: &mut Vec<Sprite>, player: &Player, maze: &Maze, block_size: usize) {
+// El sprite solo persigue cuando detecta al jugador dentro de su FOV; con varios jugadores, al más cercano
+// También elige la animación: el susto al aparecer, y luego reposo, caminar o atacar
+pub fn update_sprite_ai(sprites: &mut [Sprite], players: &[&Player], maze: &Maze, doors: &Doors, portals: &Portals, block_size: usize, delta_time: f32) {
     const ENEMY_SPEED: f32 = 5.0; // Velocidad del enemigo (aumentada para mejor visibilidad)
     const ROTATION_SPEED: f32 = 0.08; // Velocidad de rotación hacia el jugador
-    const COLLISION_MARGIN: f32 = 12.0; // Margen de seguridad para colisiones
     const DETECTION_HYSTERESIS: f32 = std::f32::consts::PI * 0.1; // Histeresis para evitar parpadeos
+    const ATTACK_RANGE: f32 = 60.0; // Distancia a la que el sprite pasa a la animación de ataque
+    const SCARE_DURATION: f32 = 1.0; // Segundos de la animación de susto al aparecer
     
     for sprite in sprites.iter_mut() {
-        // Calcular dirección hacia el jugador
-        let dx = player.pos.x - sprite.pos.x;
-        let dy = player.pos.y - sprite.pos.y;
-        let distance = (dx * dx + dy * dy).sqrt();
-        
-        // Calcular ángulo hacia el jugador
-        let angle_to_player = dy.atan2(dx);
-        
+        let distance_to = |player: &&&Player| (player.pos.x - sprite.pos.x).hypot(player.pos.y - sprite.pos.y);
+        let Some(player) = players.iter().min_by(|a, b| distance_to(a).total_cmp(&distance_to(b))) else {
+            return;
+        };
+        sprite.state_time += delta_time;
+        let scaring = sprite.state == SpriteState::Scare && sprite.state_time < SCARE_DURATION;
+
         // Verificar si el sprite puede ver al jugador
-        let can_see = sprite.can_see_player(player.pos, maze, block_size);
+        let detection_range = if player.crouching { CROUCH_DETECTION_RANGE } else { DETECTION_RANGE };
+        let sighting = sprite.can_see_player(player.pos, detection_range, maze, doors, portals, block_size);
+        let can_see = sighting.is_some();
+
+        // Dirección y distancia hacia el jugador: las de la línea de visión si lo ve (puede
+        // pasar por un portal) y si no, en línea recta
+        let (angle_to_player, distance) = match sighting {
+            Some(sighting) => (sighting.angle, sighting.distance),
+            None => {
+                let dx = player.pos.x - sprite.pos.x;
+                let dy = player.pos.y - sprite.pos.y;
+                (dy.atan2(dx), dx.hypot(dy))
+            }
+        };
         
         // Usar histeresis: una vez detectado, mantener detección con un FOV más amplio
         let detection_fov = if sprite.player_detected {
@@ -267,12 +440,25 @@ pub fn update_sprite_ai(sprites: &mut Vec<Sprite>, player: &Player, maze: &Maze,
         
         // Solo perseguir si detectó al jugador
         if !detected || distance < 15.0 {
+            if !scaring {
+                sprite.set_state(if detected { SpriteState::Attack } else { SpriteState::Idle });
+            }
             continue;
         }
         
+        // Si lo ve a través de un portal, ir hacia el portal: al entrar en la celda el sprite
+        // sale por la pareja, del lado del jugador
+        let (target_x, target_y) = match sighting.and_then(|sighting| sighting.portal) {
+            Some((col, row)) => ((col as f32 + 0.5) * block_size as f32, (row as f32 + 0.5) * block_size as f32),
+            None => (player.pos.x, player.pos.y),
+        };
+        let dx = target_x - sprite.pos.x;
+        let dy = target_y - sprite.pos.y;
+        let target_distance = dx.hypot(dy);
+
         // Normalizar dirección
-        let dir_x = if distance > 0.0 { dx / distance } else { 0.0 };
-        let dir_y = if distance > 0.0 { dy / distance } else { 0.0 };
+        let dir_x = if target_distance > 0.0 { dx / target_distance } else { 0.0 };
+        let dir_y = if target_distance > 0.0 { dy / target_distance } else { 0.0 };
         
         // Calcular movimiento deseado (moverse en la dirección hacia el jugador)
         let move_x = dir_x * ENEMY_SPEED;
@@ -286,18 +472,18 @@ pub fn update_sprite_ai(sprites: &mut Vec<Sprite>, player: &Player, maze: &Maze,
         let mut moved = false;
         
         // Primero intentar moverse en diagonal (dirección completa)
-        if !check_collision_with_margin(maze, new_x, new_y, block_size, COLLISION_MARGIN) {
+        if !check_collision_with_margin(maze, doors, new_x, new_y, block_size, SPRITE_COLLISION_MARGIN) {
             sprite.pos.x = new_x;
             sprite.pos.y = new_y;
             moved = true;
         } else {
             // Si hay colisión en diagonal, intentar solo en X
-            if !check_collision_with_margin(maze, new_x, sprite.pos.y, block_size, COLLISION_MARGIN) {
+            if !check_collision_with_margin(maze, doors, new_x, sprite.pos.y, block_size, SPRITE_COLLISION_MARGIN) {
                 sprite.pos.x = new_x;
                 moved = true;
             }
             // Intentar solo en Y
-            if !check_collision_with_margin(maze, sprite.pos.x, new_y, block_size, COLLISION_MARGIN) {
+            if !check_collision_with_margin(maze, doors, sprite.pos.x, new_y, block_size, SPRITE_COLLISION_MARGIN) {
                 sprite.pos.y = new_y;
                 moved = true;
             }
@@ -316,12 +502,113 @@ pub fn update_sprite_ai(sprites: &mut Vec<Sprite>, player: &Player, maze: &Maze,
                 let alt_x = sprite.pos.x + perp_x * ENEMY_SPEED;
                 let alt_y = sprite.pos.y + perp_y * ENEMY_SPEED;
                 
-                if !check_collision_with_margin(maze, alt_x, alt_y, block_size, COLLISION_MARGIN) {
+                if !check_collision_with_margin(maze, doors, alt_x, alt_y, block_size, SPRITE_COLLISION_MARGIN) {
                     sprite.pos.x = alt_x;
                     sprite.pos.y = alt_y;
+                    moved = true;
                     break;
                 }
             }
         }
+
+        if !scaring {
+            let state = if distance < ATTACK_RANGE {
+                SpriteState::Attack
+            } else if moved {
+                SpriteState::Walk
+            } else {
+                SpriteState::Idle
+            };
+            sprite.set_state(state);
+        }
+
+        // Los sprites también cruzan los portales
+        if let Some((x, y, facing_angle)) = portals.teleport(sprite.pos.x, sprite.pos.y, sprite.facing_angle, block_size) {
+            sprite.pos.x = x;
+            sprite.pos.y = y;
+            sprite.facing_angle = facing_angle;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn maze_from(rows: &[&str]) -> Maze {
+        rows.iter().map(|row| row.chars().collect()).collect()
+    }
+
+    #[test]
+    fn sprite_chases_the_player_through_a_portal() {
+        // Dos corredores sin conexión: el portal '1' en (1, 1) lleva al (5, 3)
+        let maze = maze_from(&["#######", "#1   ##", "#######", "##   1#", "#######"]);
+        let doors = Doors::new(&maze);
+        let portals = Portals::new(&maze);
+        let player = Player::new(250.0, 350.0, 0.0, PI / 3.0);
+
+        let mut sprite = Sprite::new(350.0, 150.0, 'g');
+        sprite.facing_angle = PI;
+        let sighting = sprite.can_see_player(player.pos, DETECTION_RANGE, &maze, &doors, &portals, 100).unwrap();
+        assert_eq!(sighting.portal, Some((1, 1)));
+
+        // Camina hacia el portal, sale por la pareja y sigue hasta el jugador
+        let mut sprites = vec![sprite];
+        for _ in 0..120 {
+            update_sprite_ai(&mut sprites, &[&player], &maze, &doors, &portals, 100, 1.0 / 60.0);
+        }
+        assert!(sprites[0].player_detected);
+        assert!((300.0..400.0).contains(&sprites[0].pos.y));
+        assert!((sprites[0].pos.x - player.pos.x).hypot(sprites[0].pos.y - player.pos.y) < 20.0);
+    }
+
+    #[test]
+    fn view_from_counts_eighths_clockwise_from_the_front() {
+        let mut sprite = Sprite::new(100.0, 100.0, 'e');
+        sprite.facing_angle = 0.0; // mirando al este
+        assert_eq!(sprite.view_from(Vector2::new(200.0, 100.0)), 0); // de frente
+        assert_eq!(sprite.view_from(Vector2::new(200.0, 200.0)), 1);
+        assert_eq!(sprite.view_from(Vector2::new(100.0, 200.0)), 2); // su derecha es el sur
+        assert_eq!(sprite.view_from(Vector2::new(0.0, 100.0)), 4); // de espaldas
+        assert_eq!(sprite.view_from(Vector2::new(100.0, 0.0)), 6); // su izquierda es el norte
+        assert_eq!(sprite.view_from(Vector2::new(200.0, 90.0)), 0); // casi de frente, por el otro lado
+
+        // Girar el sprite gira las vistas con él
+        sprite.facing_angle = PI / 2.0; // mirando al sur
+        assert_eq!(sprite.view_from(Vector2::new(100.0, 200.0)), 0);
+        assert_eq!(sprite.view_from(Vector2::new(0.0, 100.0)), 2);
+    }
+
+    fn same_frame(a: TextureRef, b: TextureRef) -> bool {
+        a.width() == b.width()
+            && a.height() == b.height()
+            && (0..a.width()).all(|x| (0..a.height()).all(|y| a.texel(x, y) == b.texel(x, y)))
+    }
+
+    #[test]
+    fn frame_from_follows_view_state_and_time() {
+        let texture_manager = TextureManager::load_from_disk();
+        let mut sprite = Sprite::new(100.0, 100.0, 'e');
+        sprite.set_state(SpriteState::Idle);
+        let front = Vector2::new(200.0, 100.0);
+        let back = Vector2::new(0.0, 100.0);
+
+        let idle_front = sprite.frame_from(front, &texture_manager).unwrap();
+        assert!(!same_frame(idle_front, sprite.frame_from(back, &texture_manager).unwrap()));
+
+        // La hoja de reposo tiene 2 cuadros a 2 fps: a los 0.5 s toca el segundo
+        sprite.state_time = 0.5;
+        assert!(!same_frame(idle_front, sprite.frame_from(front, &texture_manager).unwrap()));
+        sprite.state_time = 1.0;
+        assert!(same_frame(idle_front, sprite.frame_from(front, &texture_manager).unwrap()));
+
+        // Sin hoja de ataque se usa la de reposo
+        sprite.state_time = 0.0;
+        sprite.state = SpriteState::Attack;
+        assert!(same_frame(idle_front, sprite.frame_from(front, &texture_manager).unwrap()));
+
+        // Un sprite sin hojas se ve igual desde todos lados
+        let scare = Sprite::new(100.0, 100.0, 's');
+        assert!(same_frame(scare.frame_from(front, &texture_manager).unwrap(), scare.frame_from(back, &texture_manager).unwrap()));
     }
 }
@@ -1,79 +1,528 @@
 // textures.rs
 
 use raylib::prelude::*;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::sprites::{SpriteState, SPRITE_VIEWS};
+
+// Texels de una imagen ya convertidos a RGBA, por columnas: las paredes se muestrean de arriba
+// abajo dentro de una misma columna, así que los texels que se leen seguidos quedan juntos
+struct TexelBuffer {
+    width: u32,
+    height: u32,
+    texels: Vec<Color>, // columna x, fila y en texels[x * height + y]
+}
+
+impl TexelBuffer {
+    // Convertir una imagen de cualquier formato de raylib (se hace una sola vez al cargar)
+    fn from_image(image: &Image) -> Self {
+        let width = image.width.max(1) as u32;
+        let height = image.height.max(1) as u32;
+        let rows = image.get_image_data();
+        let mut texels = vec![Color::WHITE; (width * height) as usize];
+        for (index, color) in rows.iter().enumerate().take(texels.len()) {
+            let (x, y) = (index as u32 % width, index as u32 / width);
+            texels[(x * height + y) as usize] = *color;
+        }
+        TexelBuffer { width, height, texels }
+    }
+
+    // Imagen de raylib con los mismos texels (para subirla a la GPU)
+    fn to_image(&self) -> Image {
+        let image = Image::gen_image_color(self.width as i32, self.height as i32, Color::BLACK);
+        let mut bytes = Vec::with_capacity(self.texels.len() * 4);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let color = self.texel(x, y);
+                bytes.extend_from_slice(&[color.r, color.g, color.b, color.a]);
+            }
+        }
+        // SAFETY: gen_image_color crea una imagen R8G8B8A8 de width * height píxeles,
+        // exactamente el tamaño de `bytes`
+        unsafe {
+            std::ptr::copy_nonoverlapping(bytes.as_ptr(), image.data as *mut u8, bytes.len());
+        }
+        image
+    }
+
+    // Color de un texel (las coordenadas fuera de rango se recortan al borde)
+    #[inline]
+    fn texel(&self, x: u32, y: u32) -> Color {
+        let x = x.min(self.width - 1);
+        let y = y.min(self.height - 1);
+        self.texels[(x * self.height + y) as usize]
+    }
+}
+
+// Cadena de mipmaps: el nivel 0 es la imagen original y cada nivel mide la mitad del anterior
+type MipChain = Vec<TexelBuffer>;
+
+// Textura animada: sus cuadros avanzan a `fps` cuadros por segundo
+struct Animation {
+    frames: Vec<MipChain>,
+    fps: f32,
+}
+
+// Hoja de un sprite con vistas: una fila de cuadros por cada ángulo desde el que se lo ve
+struct SpriteSheet {
+    views: Vec<Vec<MipChain>>, // SPRITE_VIEWS filas de cuadros
+    fps: f32,
+}
+
+// Cómo se leen los texels al muestrear una textura con mipmaps
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TextureSampling {
+    Nearest,
+    Bilinear,
+}
+
+// Archivo con el carácter y la imagen de cada textura
+const TEXTURE_MANIFEST: &str = "assets/textures.txt";
+
+// Una entrada del manifiesto o de una línea '#texture' de un nivel
+#[derive(Debug, Clone, PartialEq)]
+struct TextureSpec {
+    ch: char,
+    path: String,
+    animation: Option<(usize, f32)>, // cuadros y cuadros por segundo, si es animada
+    state: Option<SpriteState>,      // si es la hoja con vistas de un estado de un sprite
+}
+
+impl TextureSpec {
+    // "<carácter> <archivo> [cuadros fps [estado]]"
+    fn parse(parts: &[&str]) -> Option<TextureSpec> {
+        let (ch, path, animation, state) = match parts {
+            [ch, path] => (ch, path, None, None),
+            [ch, path, frames, fps] => (ch, path, Some((frames.parse().ok()?, fps.parse().ok()?)), None),
+            [ch, path, frames, fps, state] => (
+                ch,
+                path,
+                Some((frames.parse().ok()?, fps.parse().ok()?)),
+                Some(SpriteState::parse(state)?),
+            ),
+            _ => return None,
+        };
+        let mut chars = ch.chars();
+        let (Some(ch), None) = (chars.next(), chars.next()) else {
+            return None;
+        };
+        Some(TextureSpec { ch, path: path.to_string(), animation, state })
+    }
+
+    // Dos entradas con la misma clave definen la misma textura (una reemplaza a la otra)
+    fn key(&self) -> (char, Option<SpriteState>) {
+        (self.ch, self.state)
+    }
+}
 
 pub struct TextureManager {
-    images: HashMap<char, Image>,       // Store images for pixel access
+    images: HashMap<char, MipChain>,    // Texels en memoria para el render por software
     textures: HashMap<char, Texture2D>, // Store GPU textures for rendering
+    animations: HashMap<char, Animation>, // Cuadros de las texturas animadas
+    sheets: HashMap<(char, SpriteState), SpriteSheet>, // Hojas con vistas de los sprites
+    manifest: Vec<TextureSpec>,         // Texturas de todos los niveles
+    level_specs: Vec<TextureSpec>,      // Texturas que cambió el nivel actual
+    stale_textures: HashSet<char>,      // Caracteres cuya textura GPU ya no coincide con sus texels
+    time: f32,                          // Tiempo de juego con el que se elige el cuadro actual
 }
 
 impl TextureManager {
     pub fn new(rl: &mut RaylibHandle, thread: &RaylibThread) -> Self {
-        let mut images = HashMap::new();
-        let mut textures = HashMap::new();
+        let mut texture_manager = TextureManager::load_from_disk();
+        texture_manager.upload_textures(rl, thread);
+        texture_manager
+    }
 
-        // Map characters to texture file paths
-        let texture_files = vec![
-            ('+', "assets/wallU.png"),
-            ('-', "assets/wallU.png"),
-            ('|', "assets/wallU.png"),
-            ('g', "assets/wall5.png"),
-            ('#', "assets/wall3.png"), // default/fallback
-            ('e', "assets/SpookyBG.png"), // sprite de enemigo
-            ('t', "assets/SpookyBG.png"), // trigger sprite SpookyBG
-            ('s', "assets/SCARE_01.png"), // trigger sprite SCARE_01
-            ('c', "assets/SCARE_05.png"), // trigger sprite SCARE_05
-        ];
+    // Subir a la GPU las texturas que cambiaron desde la última subida (todas, la primera vez):
+    // cada imagen y el primer cuadro de cada animación. Llamar después de `load_level_textures`
+    // y de `reload_files`; las hojas de sprites solo se dibujan por software y no se suben
+    pub fn upload_textures(&mut self, rl: &mut RaylibHandle, thread: &RaylibThread) {
+        for ch in std::mem::take(&mut self.stale_textures) {
+            let first_image = self
+                .images
+                .get(&ch)
+                .or_else(|| self.animations.get(&ch).map(|animation| &animation.frames[0]));
+            let Some(mips) = first_image else {
+                self.textures.remove(&ch);
+                continue;
+            };
+            match rl.load_texture_from_image(thread, &mips[0].to_image()) {
+                Ok(texture) => {
+                    self.textures.insert(ch, texture);
+                }
+                Err(_) => {
+                    self.textures.remove(&ch);
+                    eprintln!("Advertencia: No se pudo subir la textura de '{}' a la GPU", ch);
+                }
+            }
+        }
+    }
+
+    // Cargar solo las imágenes (CPU), sin ventana ni texturas GPU; basta para el render por software
+    pub fn load_from_disk() -> Self {
+        let mut texture_manager = TextureManager {
+            images: HashMap::new(),
+            textures: HashMap::new(),
+            animations: HashMap::new(),
+            sheets: HashMap::new(),
+            manifest: read_manifest(),
+            level_specs: Vec::new(),
+            stale_textures: HashSet::new(),
+            time: 0.0,
+        };
+        for spec in texture_manager.manifest.clone() {
+            texture_manager.load_spec(&spec);
+        }
+        texture_manager
+    }
 
-        for (ch, path) in texture_files {
-            let image = Image::load_image(path).expect(&format!("Failed to load image {}", path));
-            let texture = rl.load_texture(thread, path).expect(&format!("Failed to load texture {}", path));
-            images.insert(ch, image);
-            textures.insert(ch, texture);
+    // Aplicar las líneas '#texture' de un nivel; las texturas que cambió el nivel anterior
+    // vuelven a ser las del manifiesto. Las texturas GPU se actualizan con `upload_textures`.
+    pub fn load_level_textures(&mut self, level_file: &str) {
+        for previous in std::mem::take(&mut self.level_specs) {
+            self.unload(&previous);
+            if let Some(spec) = self.manifest.iter().find(|spec| spec.key() == previous.key()).cloned() {
+                self.load_spec(&spec);
+            }
         }
 
-        TextureManager { images, textures }
+        let Ok(text) = fs::read_to_string(level_file) else {
+            return;
+        };
+        for line in text.lines() {
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            if let ["#texture", rest @ ..] = parts.as_slice() {
+                match TextureSpec::parse(rest) {
+                    Some(spec) => {
+                        self.load_spec(&spec);
+                        self.level_specs.push(spec);
+                    }
+                    None => eprintln!("Advertencia: Línea '#texture' inválida en {}: {}", level_file, line),
+                }
+            }
+        }
     }
 
-    pub fn get_pixel_color(&self, ch: char, tx: u32, ty: u32) -> Color {
-        if let Some(image) = self.images.get(&ch) {
-            let x = tx.min(image.width as u32 - 1) as i32;
-            let y = ty.min(image.height as u32 - 1) as i32;
-            
-            // Usar la función nativa de Raylib para obtener el color del píxel
-            unsafe {
-                let raylib_color = raylib::ffi::GetImageColor(
-                    raylib::ffi::Image {
-                        data: image.data,
-                        width: image.width,
-                        height: image.height,
-                        mipmaps: image.mipmaps,
-                        format: image.format,
-                    },
-                    x,
-                    y
-                );
-                
-                 // Manejar diferentes formatos de imagen
-                 let alpha = match image.format {
-                     4 => raylib_color.a, // PIXELFORMAT_UNCOMPRESSED_R8G8B8A8
-                     3 => 255, // PIXELFORMAT_UNCOMPRESSED_R8G8B8 (sin alpha)
-                     _ => 255, // Por defecto, completamente opaco
-                 };
-                
-                Color::new(raylib_color.r, raylib_color.g, raylib_color.b, alpha)
+    // Archivos de los que salen las texturas actuales: el manifiesto y cada imagen
+    // (de las texturas con PNGs numerados, también el siguiente número, por si se agrega un cuadro)
+    pub fn source_files(&self) -> Vec<PathBuf> {
+        let mut files = vec![PathBuf::from(TEXTURE_MANIFEST)];
+        for spec in self.manifest.iter().chain(&self.level_specs) {
+            if spec.path.contains("{}") {
+                let frame_count = self.animations.get(&spec.ch).map_or(0, |animation| animation.frames.len());
+                files.extend((0..=frame_count).map(|index| PathBuf::from(spec.path.replace("{}", &index.to_string()))));
+            } else {
+                files.push(PathBuf::from(&spec.path));
+            }
+        }
+        files
+    }
+
+    // Volver a cargar las texturas cuyos archivos cambiaron; si cambió el manifiesto se recargan
+    // todas. Las texturas del nivel se vuelven a aplicar encima. Las texturas GPU se actualizan
+    // con `upload_textures`.
+    pub fn reload_files(&mut self, changed: &[PathBuf]) {
+        let manifest_changed = changed.iter().any(|path| path == Path::new(TEXTURE_MANIFEST));
+        if manifest_changed {
+            self.stale_textures.extend(self.images.keys().chain(self.animations.keys()));
+            self.images.clear();
+            self.animations.clear();
+            self.sheets.clear();
+            self.manifest = read_manifest();
+        }
+
+        let is_changed = |spec: &TextureSpec| {
+            manifest_changed
+                || changed.iter().any(|path| {
+                    let path = path.to_string_lossy();
+                    match spec.path.split_once("{}") {
+                        Some((prefix, suffix)) => path.starts_with(prefix) && path.ends_with(suffix),
+                        None => path == spec.path,
+                    }
+                })
+        };
+        // Las del manifiesto que el nivel reemplaza no se tocan
+        let overridden = |spec: &TextureSpec| self.level_specs.iter().any(|level_spec| level_spec.key() == spec.key());
+        let specs: Vec<TextureSpec> = self
+            .manifest
+            .iter()
+            .filter(|spec| !overridden(spec))
+            .chain(&self.level_specs)
+            .filter(|spec| is_changed(spec))
+            .cloned()
+            .collect();
+        for spec in specs {
+            self.load_spec(&spec);
+        }
+    }
+
+    // Cargar una textura (estática o animada); si el archivo no carga se registra el tablero de
+    // "textura faltante" para que el error se vea en el juego sin detenerlo
+    fn load_spec(&mut self, spec: &TextureSpec) {
+        self.unload(spec);
+        if let Some(state) = spec.state {
+            let frame_count = spec.animation.map_or(1, |(frame_count, _)| frame_count);
+            let fps = spec.animation.map_or(0.0, |(_, fps)| fps);
+            let views = match Image::load_image(&spec.path) {
+                Ok(sheet) => split_sheet(&sheet, frame_count),
+                Err(_) => {
+                    eprintln!("Advertencia: No se pudo cargar la hoja {} ('{}'); se usa la textura de reemplazo", spec.path, spec.ch);
+                    (0..SPRITE_VIEWS).map(|_| vec![build_mip_chain(missing_texture())]).collect()
+                }
+            };
+            self.sheets.insert((spec.ch, state), SpriteSheet { views, fps });
+            return;
+        }
+
+        let frames = match spec.animation {
+            Some((frame_count, _)) => load_frames(&spec.path, frame_count),
+            None => Image::load_image(&spec.path).into_iter().collect(),
+        };
+        if frames.is_empty() {
+            eprintln!("Advertencia: No se pudo cargar la textura {} ('{}'); se usa la textura de reemplazo", spec.path, spec.ch);
+            self.images.insert(spec.ch, build_mip_chain(missing_texture()));
+            return;
+        }
+
+        match spec.animation {
+            Some((_, fps)) => {
+                let frames = frames.into_iter().map(build_mip_chain).collect();
+                self.animations.insert(spec.ch, Animation { frames, fps });
+            }
+            None => {
+                let image = frames.into_iter().next().expect("hay al menos un cuadro");
+                self.images.insert(spec.ch, build_mip_chain(image));
             }
-        } else {
-            Color::WHITE
         }
     }
 
+    // Quitar lo que cargó una entrada (la hoja de un estado, o la textura del carácter)
+    fn unload(&mut self, spec: &TextureSpec) {
+        match spec.state {
+            Some(state) => {
+                self.sheets.remove(&(spec.ch, state));
+            }
+            None => {
+                self.images.remove(&spec.ch);
+                self.animations.remove(&spec.ch);
+                self.stale_textures.insert(spec.ch);
+            }
+        }
+    }
+
+    // Actualizar el tiempo de juego (llamar una vez por frame, antes de renderizar)
+    pub fn update(&mut self, time: f32) {
+        self.time = time;
+    }
+
+    // Cadena de mipmaps actual de un carácter: la del cuadro que toca según el tiempo si está animada
+    fn current_mips(&self, ch: char) -> Option<&MipChain> {
+        if let Some(animation) = self.animations.get(&ch) {
+            let frame = (self.time * animation.fps) as usize % animation.frames.len();
+            return animation.frames.get(frame);
+        }
+        self.images.get(&ch)
+    }
+
+    // Textura actual de un carácter; conviene buscarla una vez por columna o sprite
+    // y muestrear con ella, en vez de buscar el carácter en cada píxel
+    pub fn texture(&self, ch: char) -> Option<TextureRef<'_>> {
+        self.current_mips(ch).map(|mips| TextureRef { mips })
+    }
+
+    // Cuadro de un sprite visto desde la vista `view` (0..SPRITE_VIEWS), `state_time` segundos después
+    // de entrar en `state`. Sin hoja para ese estado se usa la de reposo, y sin ninguna hoja,
+    // la textura normal del carácter (la misma desde todos los ángulos)
+    pub fn sprite_frame(&self, ch: char, state: SpriteState, view: usize, state_time: f32) -> Option<TextureRef<'_>> {
+        let sheet = self.sheets.get(&(ch, state)).or_else(|| self.sheets.get(&(ch, SpriteState::Idle)));
+        let Some(sheet) = sheet else {
+            return self.texture(ch);
+        };
+        let frames = &sheet.views[view % sheet.views.len()];
+        let frame = (state_time * sheet.fps) as usize % frames.len();
+        Some(TextureRef { mips: &frames[frame] })
+    }
+
+    pub fn get_pixel_color(&self, ch: char, tx: u32, ty: u32) -> Color {
+        self.texture(ch).map_or(Color::WHITE, |texture| texture.texel(tx, ty))
+    }
+
+    // Muestrear una textura en coordenadas normalizadas (ver `TextureRef::sample`)
+    pub fn sample(&self, ch: char, u: f32, v: f32, footprint: f32, sampling: TextureSampling) -> Color {
+        self.texture(ch).map_or(Color::WHITE, |texture| texture.sample(u, v, footprint, sampling))
+    }
+
     pub fn get_texture(&self, ch: char) -> Option<&Texture2D> {
         self.textures.get(&ch)
     }
 
     pub fn get_texture_dimensions(&self, ch: char) -> Option<(u32, u32)> {
-        self.images.get(&ch).map(|img| (img.width as u32, img.height as u32))
+        self.texture(ch).map(|texture| (texture.width(), texture.height()))
+    }
+}
+
+// Una textura (el cuadro actual si es animada) con sus mipmaps
+#[derive(Clone, Copy)]
+pub struct TextureRef<'a> {
+    mips: &'a MipChain,
+}
+
+impl TextureRef<'_> {
+    pub fn width(&self) -> u32 {
+        self.mips[0].width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.mips[0].height
+    }
+
+    // Color de un texel a resolución completa
+    #[inline]
+    pub fn texel(&self, x: u32, y: u32) -> Color {
+        self.mips[0].texel(x, y)
+    }
+
+    // Muestrear en coordenadas normalizadas (u, v) en [0, 1]
+    // `footprint` es cuántos texels del nivel 0 caen en un píxel de pantalla: con valores mayores
+    // que 1 se lee un mipmap más pequeño, que ya promedia esos texels y evita el parpadeo a distancia
+    pub fn sample(&self, u: f32, v: f32, footprint: f32, sampling: TextureSampling) -> Color {
+        let level = (footprint.max(1.0).log2() as usize).min(self.mips.len() - 1);
+        let buffer = &self.mips[level];
+        let x = u.clamp(0.0, 1.0) * buffer.width as f32;
+        let y = v.clamp(0.0, 1.0) * buffer.height as f32;
+
+        match sampling {
+            TextureSampling::Nearest => buffer.texel(x as u32, y as u32),
+            TextureSampling::Bilinear => {
+                // Centros de los cuatro texels más cercanos y el peso de cada uno
+                let x = (x - 0.5).max(0.0);
+                let y = (y - 0.5).max(0.0);
+                let (x0, y0) = (x as u32, y as u32);
+                let (fx, fy) = (x.fract(), y.fract());
+                let top = lerp_color(buffer.texel(x0, y0), buffer.texel(x0 + 1, y0), fx);
+                let bottom = lerp_color(buffer.texel(x0, y0 + 1), buffer.texel(x0 + 1, y0 + 1), fx);
+                lerp_color(top, bottom, fy)
+            }
+        }
+    }
+}
+
+fn lerp_color(a: Color, b: Color, t: f32) -> Color {
+    let mix = |x: u8, y: u8| (x as f32 + (y as f32 - x as f32) * t) as u8;
+    Color::new(mix(a.r, b.r), mix(a.g, b.g), mix(a.b, b.b), mix(a.a, b.a))
+}
+
+// Generar los mipmaps de una imagen reduciéndola a la mitad hasta llegar a 1 píxel de lado
+// (el redimensionado de raylib filtra los texels, así que cada nivel es un promedio del anterior)
+// y convertir cada nivel a texels RGBA
+fn build_mip_chain(image: Image) -> MipChain {
+    let mut mips = vec![TexelBuffer::from_image(&image)];
+    let mut last = image;
+    while last.width > 1 && last.height > 1 {
+        let mut next = last.clone();
+        next.resize(last.width / 2, last.height / 2);
+        mips.push(TexelBuffer::from_image(&next));
+        last = next;
+    }
+    mips
+}
+
+// Leer el manifiesto de texturas (vacío si no se puede leer; las líneas inválidas se ignoran)
+fn read_manifest() -> Vec<TextureSpec> {
+    let text = match fs::read_to_string(TEXTURE_MANIFEST) {
+        Ok(text) => text,
+        Err(error) => {
+            eprintln!("Advertencia: No se pudo leer {}: {}", TEXTURE_MANIFEST, error);
+            return Vec::new();
+        }
+    };
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with("//"))
+        .filter_map(|line| {
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            let spec = TextureSpec::parse(&parts);
+            if spec.is_none() {
+                eprintln!("Advertencia: Línea inválida en {}: {}", TEXTURE_MANIFEST, line);
+            }
+            spec
+        })
+        .collect()
+}
+
+// Tablero magenta y negro para las texturas que no se pudieron cargar
+fn missing_texture() -> Image {
+    Image::gen_image_checked(64, 64, 8, 8, Color::MAGENTA, Color::BLACK)
+}
+
+// Separar una hoja de sprite: SPRITE_VIEWS filas (una por vista) de `frame_count` cuadros cada una
+fn split_sheet(sheet: &Image, frame_count: usize) -> Vec<Vec<MipChain>> {
+    let frame_count = frame_count.max(1);
+    let frame_width = (sheet.width / frame_count as i32) as f32;
+    let frame_height = (sheet.height / SPRITE_VIEWS as i32) as f32;
+    (0..SPRITE_VIEWS)
+        .map(|view| {
+            (0..frame_count)
+                .map(|frame| {
+                    let area = Rectangle::new(frame as f32 * frame_width, view as f32 * frame_height, frame_width, frame_height);
+                    build_mip_chain(sheet.from_image(area))
+                })
+                .collect()
+        })
+        .collect()
+}
+
+// Cargar los cuadros de una textura animada (vacío si no se pudo cargar ninguno)
+fn load_frames(path: &str, frame_count: usize) -> Vec<Image> {
+    if path.contains("{}") {
+        return (0..)
+            .map(|index| path.replace("{}", &index.to_string()))
+            .take_while(|frame_path| Path::new(frame_path).exists())
+            .map_while(|frame_path| Image::load_image(&frame_path).ok())
+            .collect();
+    }
+
+    let Ok(sheet) = Image::load_image(path) else {
+        return Vec::new();
+    };
+    let frame_count = frame_count.max(1);
+    let frame_width = sheet.width / frame_count as i32;
+    (0..frame_count)
+        .map(|index| sheet.from_image(Rectangle::new((index as i32 * frame_width) as f32, 0.0, frame_width as f32, sheet.height as f32)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(line: &str) -> Option<TextureSpec> {
+        TextureSpec::parse(&line.split_whitespace().collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn texture_spec_parses_static_animated_and_sheet_entries() {
+        assert_eq!(
+            parse("# assets/wall1.png"),
+            Some(TextureSpec { ch: '#', path: "assets/wall1.png".to_string(), animation: None, state: None })
+        );
+        assert_eq!(parse("w assets/wall_drip_{}.png 0 4").unwrap().animation, Some((0, 4.0)));
+
+        let sheet = parse("e assets/enemy_walk.png 4 8 walk").unwrap();
+        assert_eq!(sheet.animation, Some((4, 8.0)));
+        assert_eq!(sheet.state, Some(SpriteState::Walk));
+        assert_eq!(sheet.key(), ('e', Some(SpriteState::Walk)));
+    }
+
+    #[test]
+    fn texture_spec_rejects_malformed_entries() {
+        assert_eq!(parse("e"), None);
+        assert_eq!(parse("ee assets/enemy.png"), None);
+        assert_eq!(parse("w assets/wall.png cuatro 4"), None);
+        assert_eq!(parse("w assets/wall.png 4"), None);
+        assert_eq!(parse("e assets/enemy.png 4 8 dance"), None);
+        assert_eq!(parse("e assets/enemy.png 4 8 walk extra"), None);
     }
 }
@@ -1,6 +1,10 @@
 use raylib::prelude::*;
 use std::f32::consts::PI;
 
+use crate::caster::row_distance;
+use crate::framebuffer::{blend_colors, pack_color, unpack_color, BlendMode, Framebuffer};
+use crate::player::Player;
+
 #[derive(Debug, Clone)]
 pub struct VisualEffects {
     pub flashlight_enabled: bool,
@@ -8,7 +12,6 @@ pub struct VisualEffects {
     pub fog_distance: f32,
     pub anxiety_level: f32,
     pub damage_effect: f32,
-    pub time: f32,
 }
 
 impl VisualEffects {
@@ -19,13 +22,11 @@ impl VisualEffects {
             fog_distance: 300.0,
             anxiety_level: 0.0,
             damage_effect: 0.0,
-            time: 0.0,
         }
     }
 
+    // Reducir la ansiedad y el daño gradualmente (llamar una vez por frame)
     pub fn update(&mut self, delta_time: f32) {
-        self.time += delta_time;
-        
         // Reducir efectos gradualmente
         if self.anxiety_level > 0.0 {
             self.anxiety_level = (self.anxiety_level - delta_time * 0.5).max(0.0);
@@ -49,148 +50,233 @@ impl VisualEffects {
     }
 }
 
-pub fn apply_flashlight_effect(framebuffer: &mut crate::framebuffer::Framebuffer, _player: &crate::player::Player, effects: &VisualEffects) {
+// Los efectos de pantalla completa se aplican por franjas de filas en paralelo (ver `Framebuffer::par_rows`)
+
+pub fn apply_flashlight_effect(framebuffer: &mut Framebuffer, _player: &Player, effects: &VisualEffects) {
     if !effects.flashlight_enabled {
         return;
     }
 
-    let center_x = framebuffer.width / 2;
-    let center_y = framebuffer.height / 2;
-    let radius = 200.0 * effects.flashlight_intensity;
-    let transition_width = 30.0; // Ancho de la transición suave en el borde
+    let center_x = (framebuffer.width / 2) as f32;
+    let center_y = (framebuffer.height / 2) as f32;
+    // Los tamaños están pensados para 900 píxeles de alto; escalar según la resolución interna
+    let resolution_scale = framebuffer.height as f32 / 900.0;
+    let radius = 200.0 * effects.flashlight_intensity * resolution_scale;
+    let transition_width = 30.0 * resolution_scale; // Ancho de la transición suave en el borde
     
     // Crear efecto de linterna circular con un solo círculo
-    for y in 0..framebuffer.height {
-        for x in 0..framebuffer.width {
-            let dx = x as f32 - center_x as f32;
-            let dy = y as f32 - center_y as f32;
+    framebuffer.par_rows(|y, row| {
+        for (x, pixel) in row.iter_mut().enumerate() {
+            let dx = x as f32 - center_x;
+            let dy = y as f32 - center_y;
             let distance = (dx * dx + dy * dy).sqrt();
+            let current_color = unpack_color(*pixel);
             
-            let current_color = framebuffer.get_pixel(x, y);
-            let modified_color;
-            
-            if distance <= radius {
-                // Iluminar el interior del círculo
-                let brightness_factor = 1.0 + (1.0 - distance / radius) * 0.2; // Más brillo en el centro
-                modified_color = Color::new(
-                    (current_color.r as f32 * brightness_factor).min(255.0) as u8,
-                    (current_color.g as f32 * brightness_factor).min(255.0) as u8,
-                    (current_color.b as f32 * brightness_factor).min(255.0) as u8,
-                    current_color.a,
-                );
-            } else if distance <= radius + transition_width {
-                // Zona de transición suave en el borde
-                let transition_factor = (distance - radius) / transition_width;
-                let brightness = 1.0 - transition_factor * 0.85; // Transición suave hacia oscuro
-                modified_color = Color::new(
-                    (current_color.r as f32 * brightness).max(0.0) as u8,
-                    (current_color.g as f32 * brightness).max(0.0) as u8,
-                    (current_color.b as f32 * brightness).max(0.0) as u8,
-                    current_color.a,
-                );
+            let blended = if distance <= radius {
+                // Iluminar el interior del círculo: sumar una fracción del propio color
+                let boost = (1.0 - distance / radius) * 0.2; // Más brillo en el centro
+                let glow = Color::new(current_color.r, current_color.g, current_color.b, (boost * 255.0) as u8);
+                blend_colors(current_color, glow, BlendMode::Additive)
             } else {
-                // Oscurecer píxeles fuera del círculo
-                let darkness_factor = 0.15; // Factor de oscurecimiento constante
-                modified_color = Color::new(
-                    (current_color.r as f32 * darkness_factor) as u8,
-                    (current_color.g as f32 * darkness_factor) as u8,
-                    (current_color.b as f32 * darkness_factor) as u8,
-                    current_color.a,
-                );
-            }
-            
-            framebuffer.set_pixel_color(x, y, modified_color);
+                let brightness = if distance <= radius + transition_width {
+                    // Zona de transición suave en el borde
+                    let transition_factor = (distance - radius) / transition_width;
+                    1.0 - transition_factor * 0.85 // Transición suave hacia oscuro
+                } else {
+                    // Oscurecer píxeles fuera del círculo
+                    0.15 // Factor de oscurecimiento constante
+                };
+                let shade = (brightness * 255.0) as u8;
+                blend_colors(current_color, Color::new(shade, shade, shade, 255), BlendMode::Multiply)
+            };
+            *pixel = pack_color(blended);
         }
-    }
+    });
 }
 
-pub fn apply_fog_effect(framebuffer: &mut crate::framebuffer::Framebuffer, _effects: &VisualEffects) {
+// Niebla según la profundidad de cada píxel: la pared de su columna (z-buffer) o, si la fila
+// queda por encima o por debajo de esa pared, el punto donde toca el piso o el techo
+pub fn apply_fog_effect(framebuffer: &mut Framebuffer, effects: &VisualEffects, z_buffer: &[f32], player: &Player) {
     let fog_color = Color::new(100, 100, 120, 255);
-    
-    // Aplicar niebla basada en la distancia (simulada por la posición Y en el framebuffer)
-    for y in 0..framebuffer.height {
-        let distance_factor = (y as f32 / framebuffer.height as f32).min(1.0);
-        let fog_intensity = (distance_factor * 2.0).min(1.0);
-        
-        for x in 0..framebuffer.width {
-            let current_color = framebuffer.get_pixel(x, y);
-            let fogged_color = Color::new(
-                (current_color.r as f32 * (1.0 - fog_intensity) + fog_color.r as f32 * fog_intensity) as u8,
-                (current_color.g as f32 * (1.0 - fog_intensity) + fog_color.g as f32 * fog_intensity) as u8,
-                (current_color.b as f32 * (1.0 - fog_intensity) + fog_color.b as f32 * fog_intensity) as u8,
-                current_color.a,
-            );
-            framebuffer.set_pixel_color(x, y, fogged_color);
+    let screen_height = framebuffer.height as usize;
+    let horizon = player.horizon(screen_height as f32);
+    let eye_height = player.eye_height();
+
+    framebuffer.par_rows(|y, row| {
+        let plane_distance = row_distance(y as usize, screen_height, horizon, eye_height);
+        for (x, pixel) in row.iter_mut().enumerate() {
+            let depth = z_buffer.get(x).copied().unwrap_or(f32::INFINITY).min(plane_distance);
+            // Niebla exponencial: a `fog_distance` ya tapa casi dos tercios del color
+            let fog_intensity = 1.0 - (-depth / effects.fog_distance).exp();
+            let fog = Color::new(fog_color.r, fog_color.g, fog_color.b, (fog_intensity * 255.0) as u8);
+            *pixel = pack_color(blend_colors(unpack_color(*pixel), fog, BlendMode::Alpha));
         }
-    }
+    });
 }
 
-pub fn apply_anxiety_effect(framebuffer: &mut crate::framebuffer::Framebuffer, effects: &VisualEffects) {
+pub fn apply_anxiety_effect(framebuffer: &mut Framebuffer, effects: &VisualEffects, time: f32) {
     if effects.anxiety_level <= 0.0 {
         return;
     }
 
     let intensity = effects.anxiety_level;
-    let time = effects.time;
+    let (width, height) = (framebuffer.width, framebuffer.height);
+
+    // La distorsión lee píxeles vecinos, que pueden ser de otra franja: leerlos de una copia
+    let source = framebuffer.pixels().to_vec();
     
     // Efecto de distorsión/parpadeo
-    for y in 0..framebuffer.height {
-        for x in 0..framebuffer.width {
+    framebuffer.par_rows(|y, row| {
+        for (x, pixel) in row.iter_mut().enumerate() {
+            let x = x as u32;
             // Crear efecto de distorsión basado en el tiempo
             let distortion_x = (time * 10.0 + x as f32 * 0.01).sin() * intensity * 2.0;
             let distortion_y = (time * 8.0 + y as f32 * 0.01).cos() * intensity * 1.5;
             
-            let new_x = ((x as f32 + distortion_x) as u32).min(framebuffer.width - 1);
-            let new_y = ((y as f32 + distortion_y) as u32).min(framebuffer.height - 1);
+            let new_x = ((x as f32 + distortion_x) as u32).min(width - 1);
+            let new_y = ((y as f32 + distortion_y) as u32).min(height - 1);
             
             if new_x != x || new_y != y {
-                let source_color = framebuffer.get_pixel(new_x, new_y);
-                let current_color = framebuffer.get_pixel(x, y);
-                
-                // Mezclar colores para crear efecto de distorsión
-                let mixed_color = Color::new(
-                    ((source_color.r as f32 + current_color.r as f32) / 2.0) as u8,
-                    ((source_color.g as f32 + current_color.g as f32) / 2.0) as u8,
-                    ((source_color.b as f32 + current_color.b as f32) / 2.0) as u8,
-                    current_color.a,
-                );
-                framebuffer.set_pixel_color(x, y, mixed_color);
+                let source_color = unpack_color(source[(new_y * width + new_x) as usize]);
+
+                // Mezclar colores a partes iguales para crear efecto de distorsión
+                let mixed = Color::new(source_color.r, source_color.g, source_color.b, 128);
+                *pixel = pack_color(blend_colors(unpack_color(*pixel), mixed, BlendMode::Alpha));
             }
         }
-    }
+    });
 }
 
-pub fn apply_damage_effect(framebuffer: &mut crate::framebuffer::Framebuffer, effects: &VisualEffects) {
+pub fn apply_damage_effect(framebuffer: &mut Framebuffer, effects: &VisualEffects, time: f32) {
     if effects.damage_effect <= 0.0 {
         return;
     }
 
     let intensity = effects.damage_effect;
-    let time = effects.time;
     
-    // Efecto de pantalla roja parpadeante
-    for y in 0..framebuffer.height {
-        for x in 0..framebuffer.width {
-            let current_color = framebuffer.get_pixel(x, y);
-            
-            // Crear efecto de parpadeo rojo
-            let red_intensity = (time * 20.0).sin().abs() * intensity;
-            let damage_color = Color::new(
-                (current_color.r as f32 + red_intensity * 100.0).min(255.0) as u8,
-                (current_color.g as f32 * (1.0 - red_intensity * 0.5)).max(0.0) as u8,
-                (current_color.b as f32 * (1.0 - red_intensity * 0.5)).max(0.0) as u8,
-                current_color.a,
-            );
-            
-            framebuffer.set_pixel_color(x, y, damage_color);
+    // Efecto de pantalla roja parpadeante: apagar el verde y el azul y sumar rojo
+    let red_intensity = (time * 20.0).sin().abs() * intensity;
+    let tint = ((1.0 - red_intensity * 0.5) * 255.0) as u8;
+    let tint = Color::new(255, tint, tint, 255);
+    let red = Color::new(100, 0, 0, (red_intensity * 255.0) as u8);
+    framebuffer.par_rows(|_, row| {
+        for pixel in row.iter_mut() {
+            let color = blend_colors(unpack_color(*pixel), tint, BlendMode::Multiply);
+            *pixel = pack_color(blend_colors(color, red, BlendMode::Additive));
         }
+    });
+}
+
+// Datos del frame que recibe cada efecto de post-procesado
+pub struct EffectContext<'a> {
+    pub player: &'a Player,
+    pub effects: &'a VisualEffects,
+    pub z_buffer: &'a [f32], // distancia a la pared de cada columna (INFINITY si no hay pared)
+    pub time: f32,           // segundos de juego transcurridos
+}
+
+// Un paso de post-procesado sobre la imagen ya renderizada
+pub trait PostEffect {
+    // Nombre con el que se activa o desactiva (también en los archivos de nivel)
+    fn name(&self) -> &'static str;
+    fn apply(&self, framebuffer: &mut Framebuffer, ctx: &EffectContext);
+}
+
+pub struct FogEffect;
+pub struct FlashlightEffect;
+pub struct AnxietyEffect;
+pub struct DamageEffect;
+
+impl PostEffect for FogEffect {
+    fn name(&self) -> &'static str {
+        "fog"
+    }
+    fn apply(&self, framebuffer: &mut Framebuffer, ctx: &EffectContext) {
+        apply_fog_effect(framebuffer, ctx.effects, ctx.z_buffer, ctx.player);
     }
 }
 
-pub fn render_visual_effects(framebuffer: &mut crate::framebuffer::Framebuffer, player: &crate::player::Player, effects: &VisualEffects) {
-    // Aplicar efectos en orden
-    apply_fog_effect(framebuffer, effects);
-    apply_flashlight_effect(framebuffer, player, effects);
-    apply_anxiety_effect(framebuffer, effects);
-    apply_damage_effect(framebuffer, effects);
+impl PostEffect for FlashlightEffect {
+    fn name(&self) -> &'static str {
+        "flashlight"
+    }
+    fn apply(&self, framebuffer: &mut Framebuffer, ctx: &EffectContext) {
+        apply_flashlight_effect(framebuffer, ctx.player, ctx.effects);
+    }
+}
+
+impl PostEffect for AnxietyEffect {
+    fn name(&self) -> &'static str {
+        "anxiety"
+    }
+    fn apply(&self, framebuffer: &mut Framebuffer, ctx: &EffectContext) {
+        apply_anxiety_effect(framebuffer, ctx.effects, ctx.time);
+    }
+}
+
+impl PostEffect for DamageEffect {
+    fn name(&self) -> &'static str {
+        "damage"
+    }
+    fn apply(&self, framebuffer: &mut Framebuffer, ctx: &EffectContext) {
+        apply_damage_effect(framebuffer, ctx.effects, ctx.time);
+    }
+}
+
+struct Pass {
+    effect: Box<dyn PostEffect>,
+    enabled: bool,
+}
+
+// Lista ordenada de efectos; cada uno se puede activar o desactivar en tiempo de ejecución
+pub struct PostPipeline {
+    passes: Vec<Pass>,
+}
+
+impl PostPipeline {
+    pub fn new() -> Self {
+        PostPipeline { passes: Vec::new() }
+    }
+
+    // Los efectos del juego en su orden habitual; la niebla empieza apagada
+    pub fn standard() -> Self {
+        PostPipeline::new()
+            .with(FogEffect, false)
+            .with(FlashlightEffect, true)
+            .with(AnxietyEffect, true)
+            .with(DamageEffect, true)
+    }
+
+    // Agregar un efecto al final de la lista
+    pub fn with(mut self, effect: impl PostEffect + 'static, enabled: bool) -> Self {
+        self.passes.push(Pass {
+            effect: Box::new(effect),
+            enabled,
+        });
+        self
+    }
+
+    fn pass_mut(&mut self, name: &str) -> Option<&mut Pass> {
+        self.passes.iter_mut().find(|pass| pass.effect.name() == name)
+    }
+
+    // Devuelve false si no hay ningún efecto con ese nombre
+    pub fn set_enabled(&mut self, name: &str, enabled: bool) -> bool {
+        self.pass_mut(name).map(|pass| pass.enabled = enabled).is_some()
+    }
+
+    pub fn toggle(&mut self, name: &str) -> bool {
+        self.pass_mut(name).map(|pass| pass.enabled = !pass.enabled).is_some()
+    }
+
+    pub fn is_enabled(&self, name: &str) -> bool {
+        self.passes.iter().any(|pass| pass.enabled && pass.effect.name() == name)
+    }
+
+    // Aplicar en orden los efectos activos
+    pub fn apply(&self, framebuffer: &mut Framebuffer, ctx: &EffectContext) {
+        for pass in self.passes.iter().filter(|pass| pass.enabled) {
+            pass.effect.apply(framebuffer, ctx);
+        }
+    }
 }
@@ -0,0 +1,208 @@
+// worker_pool.rs - Hilos de trabajo persistentes para repartir el render entre los núcleos
+//
+// Los hilos se crean una sola vez (la primera vez que se pide el pool) y quedan esperando
+// trabajos; así cada frame reparte las columnas y las franjas de filas sin crear hilos nuevos.
+// El hilo que llama también procesa trabajos mientras espera a que terminen los demás.
+
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Condvar, Mutex, MutexGuard, OnceLock};
+use std::thread;
+
+// Trabajo en curso: `jobs` llamadas a `f`, una por índice
+#[derive(Clone, Copy)]
+struct Task {
+    f: &'static (dyn Fn(usize) + Sync),
+    jobs: usize,
+}
+
+struct State {
+    task: Option<Task>,
+    next_job: usize, // siguiente índice sin repartir
+    pending: usize,  // índices que todavía no terminaron
+    panicked: bool,
+}
+
+struct Shared {
+    state: Mutex<State>,
+    work_ready: Condvar,
+    work_done: Condvar,
+}
+
+pub struct WorkerPool {
+    shared: &'static Shared,
+    workers: usize,
+    running: Mutex<()>, // un solo trabajo a la vez
+}
+
+impl WorkerPool {
+    // Pool con `workers` hilos además del que llama (0 = todo en el hilo que llama)
+    pub fn new(workers: usize) -> Self {
+        // Los hilos viven lo que el programa, así que el estado compartido también
+        let shared: &'static Shared = Box::leak(Box::new(Shared {
+            state: Mutex::new(State { task: None, next_job: 0, pending: 0, panicked: false }),
+            work_ready: Condvar::new(),
+            work_done: Condvar::new(),
+        }));
+        for index in 0..workers {
+            thread::Builder::new()
+                .name(format!("render-{}", index))
+                .spawn(move || worker_loop(shared))
+                .expect("No se pudo crear un hilo de render");
+        }
+        WorkerPool { shared, workers, running: Mutex::new(()) }
+    }
+
+    // Pool compartido con un hilo por núcleo disponible (contando el que llama)
+    pub fn global() -> &'static WorkerPool {
+        static POOL: OnceLock<WorkerPool> = OnceLock::new();
+        POOL.get_or_init(|| {
+            let cores = thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+            WorkerPool::new(cores - 1)
+        })
+    }
+
+    // Cantidad de hilos que procesan trabajos a la vez (los del pool más el que llama)
+    pub fn threads(&self) -> usize {
+        self.workers + 1
+    }
+
+    // Llamar a `f(i)` para cada `i` en `0..jobs`, repartido entre los hilos, y esperar a que
+    // terminen todos. Si alguna llamada entra en pánico, el pánico se repite aquí
+    // No se debe llamar desde dentro de `f` (el pool atiende un solo trabajo a la vez)
+    pub fn run(&self, jobs: usize, f: impl Fn(usize) + Sync) {
+        if self.workers == 0 || jobs <= 1 {
+            (0..jobs).for_each(f);
+            return;
+        }
+
+        let _running = self.running.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let f: &(dyn Fn(usize) + Sync) = &f;
+        // SAFETY: la referencia solo se usa mientras el trabajo está publicado, y esta función
+        // no vuelve (ni siquiera con un pánico de `f`) hasta que `pending` llega a 0 y el
+        // trabajo se retira; después de eso ningún hilo puede tomar otro índice
+        let f: &'static (dyn Fn(usize) + Sync) = unsafe { std::mem::transmute(f) };
+
+        let mut state = self.shared.lock();
+        *state = State { task: Some(Task { f, jobs }), next_job: 0, pending: jobs, panicked: false };
+        self.shared.work_ready.notify_all();
+
+        // Ayudar con los trabajos que queden y después esperar a los que están en curso
+        while let Some((task, index)) = take_job(&mut state) {
+            drop(state);
+            let ok = panic::catch_unwind(AssertUnwindSafe(|| (task.f)(index))).is_ok();
+            state = self.shared.lock();
+            finish_job(self.shared, &mut state, ok);
+        }
+        while state.pending > 0 {
+            state = self.shared.work_done.wait(state).unwrap_or_else(|poisoned| poisoned.into_inner());
+        }
+        state.task = None;
+        let panicked = state.panicked;
+        drop(state);
+
+        if panicked {
+            panic!("Un trabajo del pool de render falló");
+        }
+    }
+
+    // Llamar a `f(i, &mut items[i])` para cada elemento, repartido entre los hilos
+    pub fn for_each_mut<T: Send>(&self, items: &mut [T], f: impl Fn(usize, &mut T) + Sync) {
+        let items: Vec<Mutex<&mut T>> = items.iter_mut().map(Mutex::new).collect();
+        // Cada índice lo toma un solo hilo, así que los candados nunca se disputan
+        self.run(items.len(), |index| f(index, &mut items[index].lock().unwrap()));
+    }
+
+    // Calcular `f(i)` para cada `i` en `0..jobs` en paralelo; los resultados vuelven en orden
+    pub fn map<T: Send>(&self, jobs: usize, f: impl Fn(usize) -> T + Sync) -> Vec<T> {
+        let mut results: Vec<Option<T>> = (0..jobs).map(|_| None).collect();
+        self.for_each_mut(&mut results, |index, result| *result = Some(f(index)));
+        results.into_iter().map(|result| result.expect("Falta el resultado de un trabajo")).collect()
+    }
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, State> {
+        // Los trabajos corren fuera del candado, así que un pánico no deja el estado a medias
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+// Tomar el siguiente índice sin repartir del trabajo publicado
+fn take_job(state: &mut State) -> Option<(Task, usize)> {
+    let task = state.task?;
+    if state.next_job >= task.jobs {
+        return None;
+    }
+    state.next_job += 1;
+    Some((task, state.next_job - 1))
+}
+
+fn finish_job(shared: &Shared, state: &mut State, ok: bool) {
+    state.panicked |= !ok;
+    state.pending -= 1;
+    if state.pending == 0 {
+        shared.work_done.notify_all();
+    }
+}
+
+fn worker_loop(shared: &'static Shared) {
+    let mut state = shared.lock();
+    loop {
+        match take_job(&mut state) {
+            Some((task, index)) => {
+                drop(state);
+                let ok = panic::catch_unwind(AssertUnwindSafe(|| (task.f)(index))).is_ok();
+                state = shared.lock();
+                finish_job(shared, &mut state, ok);
+            }
+            None => {
+                state = shared.work_ready.wait(state).unwrap_or_else(|poisoned| poisoned.into_inner());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn map_returns_results_in_order() {
+        let pool = WorkerPool::new(3);
+        for _ in 0..20 {
+            assert_eq!(pool.map(50, |i| i * 2), (0..50).map(|i| i * 2).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn run_calls_every_index_once() {
+        let pool = WorkerPool::new(2);
+        let calls = AtomicUsize::new(0);
+        let mut seen = vec![0; 100];
+        pool.for_each_mut(&mut seen, |_, count| {
+            *count += 1;
+            calls.fetch_add(1, Ordering::Relaxed);
+        });
+        assert!(seen.iter().all(|&count| count == 1));
+        assert_eq!(calls.load(Ordering::Relaxed), 100);
+    }
+
+    #[test]
+    fn panic_in_a_job_reaches_the_caller_and_the_pool_keeps_working() {
+        let pool = WorkerPool::new(2);
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            pool.run(10, |i| assert!(i != 7));
+        }));
+        assert!(result.is_err());
+        assert_eq!(pool.map(4, |i| i + 1), vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn pool_without_workers_runs_on_the_calling_thread() {
+        let pool = WorkerPool::new(0);
+        let caller = thread::current().id();
+        pool.run(5, |_| assert_eq!(thread::current().id(), caller));
+        assert_eq!(pool.threads(), 1);
+    }
+}